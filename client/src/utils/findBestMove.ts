@@ -33,6 +33,36 @@ export interface Difficulty {
     best_second_gap_cp?: number
 }
 
+export type PieceRole = "pawn" | "knight" | "bishop" | "rook" | "queen" | "king"
+
+export interface SquareDetail {
+    square: string
+    /** 0-based: 0 is the a-file. */
+    file: number
+    /** 0-based: 0 is the first rank. */
+    rank: number
+}
+
+export interface MoveDetail {
+    uci: string
+    san: string
+    color: "white" | "black"
+    piece: PieceRole
+    from: SquareDetail
+    /** For castling, the king's destination. */
+    to: SquareDetail
+    captured?: PieceRole
+    /** Differs from `to` for en passant. */
+    capture_square?: SquareDetail
+    promotion?: PieceRole
+    en_passant: boolean
+    castling?: {
+        side: "king_side" | "queen_side"
+        rook_from: SquareDetail
+        rook_to: SquareDetail
+    }
+}
+
 export interface AnalysisLine {
     rank: number
     depth?: number
@@ -40,6 +70,103 @@ export interface AnalysisLine {
     score_cp?: number
     mate?: number
     pv: string[]
+    move_san?: string
+    move_detail?: MoveDetail
+    pv_san?: string[]
+    /** Numbered SAN, such as "12... Nxe4 13. Bxe4 d5". */
+    pv_text?: string
+    /** Index into `pv` of the first move that was not legal when replayed. */
+    pv_illegal_index?: number
+}
+
+export type TablebaseWdl = "win" | "cursed_win" | "draw" | "blessed_loss" | "loss"
+
+export interface TablebaseMove {
+    uci: string
+    san: string
+    /** For the side making the move. */
+    wdl: TablebaseWdl
+    dtz: number
+    zeroing: boolean
+    checkmate: boolean
+}
+
+export interface TablebaseProbe {
+    pieces: number
+    wdl: TablebaseWdl
+    /** Plies to a capture or pawn move, negative when losing. */
+    dtz: number
+    moves: TablebaseMove[]
+}
+
+export interface Threat {
+    /** The analysed position with the other side to move. */
+    fen: string
+    move_uci: string
+    move_san: string
+    move_detail: MoveDetail
+    score_cp?: number
+    mate?: number
+    /** Centipawns the extra move is worth to the opponent. */
+    gain_cp?: number
+    depth?: number
+    pv: string[]
+    pv_san: string[]
+}
+
+export interface Opening {
+    /** ECO code, e.g. "C50". */
+    eco: string
+    name: string
+    /** Half-moves in the opening's defining line. */
+    ply: number
+}
+
+export interface PlacedPiece {
+    piece: PieceRole
+    square: SquareDetail
+}
+
+export interface MaterialCount {
+    pawns: number
+    knights: number
+    bishops: number
+    rooks: number
+    queens: number
+    /** Pawns 1, minor pieces 3, rooks 5 and queens 9. */
+    points: number
+    bishop_pair: boolean
+}
+
+export interface PositionReport {
+    side_to_move: "white" | "black"
+    in_check: boolean
+    legal_move_count: number
+    /** `balance` is White's points minus Black's. */
+    material: { white: MaterialCount; black: MaterialCount; balance: number }
+    phase: "opening" | "middlegame" | "endgame"
+    phase_score: number
+    /** Pieces attacked and not defended. */
+    hanging: { white: PlacedPiece[]; black: PlacedPiece[] }
+    pinned: {
+        white: (PlacedPiece & { pinned_by: SquareDetail })[]
+        black: (PlacedPiece & { pinned_by: SquareDetail })[]
+    }
+    checks: MoveDetail[]
+    captures: MoveDetail[]
+    castling: {
+        white: { king_side: boolean; queen_side: boolean }
+        black: { king_side: boolean; queen_side: boolean }
+    }
+    en_passant?: SquareDetail
+}
+
+export interface BookMove {
+    uci: string
+    san: string
+    weight: number
+    /** Share of the total book weight, from 0 to 1. */
+    probability: number
 }
 
 export interface AnalyzeEngineInfo {
@@ -47,16 +174,43 @@ export interface AnalyzeEngineInfo {
     status: string
 }
 
+export interface GameOver {
+    outcome: "white_wins" | "black_wins" | "draw"
+    reason:
+        | "checkmate"
+        | "stalemate"
+        | "insufficient_material"
+        | "fifty_move_rule"
+        | "seventy_five_move_rule"
+}
+
 export interface AnalyzeSuccessResponse {
     ok: true
     request_id: string
-    best_move: string
+    /** Missing only when `game_over` is set. */
+    best_move?: string
     ponder?: string
+    best_move_detail?: MoveDetail
+    ponder_detail?: MoveDetail
+    fen_after_best_move?: string
+    game_over?: GameOver
     depth: number
     time_taken_ms: number
     difficulty?: Difficulty
     lines: AnalysisLine[]
     engine: AnalyzeEngineInfo
+    /** True when the server answered from its analysis cache. */
+    cached?: boolean
+    source?: "engine" | "cache" | "book" | "tablebase" | "game_over"
+    /** Every move the opening book knows, heaviest first, when `source` is "book". */
+    book_moves?: BookMove[]
+    /** The Syzygy result and every move ranked, when `source` is "tablebase". */
+    tablebase?: TablebaseProbe
+    /** The opponent's best move if the side to move passed, when `include_threat` was sent. */
+    threat?: Threat
+    opening?: Opening
+    /** Material, hanging pieces, checks and the like, read off the board. */
+    position_report?: PositionReport
 }
 
 export interface AnalyzeErrorResponse {
@@ -72,6 +226,8 @@ export type AnalyzeResponse = AnalyzeSuccessResponse | AnalyzeErrorResponse
 export interface MoveSuccess extends AnalyzeSuccessResponse {
     success: true
 
+    best_move: string
+
     /**
      * Alias for `best_move`, kept for existing caller code.
      */
@@ -206,6 +362,15 @@ export default function findBestMove(
         )
     }
 
+    if (data.game_over) {
+        return fail(
+            `game is over: ${data.game_over.reason}`,
+            "game_over",
+            response.StatusCode,
+            response.StatusMessage
+        )
+    }
+
     const move = data.best_move
     if (!move) return fail("server did not return a best move")
 
@@ -231,13 +396,23 @@ export default function findBestMove(
         success: true,
         ok: data.ok,
         request_id: data.request_id,
-        best_move: data.best_move,
+        best_move: move,
         ponder: data.ponder,
+        best_move_detail: data.best_move_detail,
+        ponder_detail: data.ponder_detail,
+        fen_after_best_move: data.fen_after_best_move,
         depth: data.depth,
         time_taken_ms: data.time_taken_ms,
         difficulty: data.difficulty,
         lines: data.lines,
         engine: data.engine,
+        cached: data.cached,
+        source: data.source,
+        book_moves: data.book_moves,
+        tablebase: data.tablebase,
+        threat: data.threat,
+        opening: data.opening,
+        position_report: data.position_report,
         move,
         piece,
         destination,
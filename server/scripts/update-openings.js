@@ -0,0 +1,20 @@
+// Rebuilds src-tauri/src/engine/openings.tsv from the lichess chess-openings
+// tables (CC0): https://github.com/lichess-org/chess-openings
+import { writeFile } from 'node:fs/promises';
+
+const BASE = 'https://raw.githubusercontent.com/lichess-org/chess-openings/master';
+const TARGET = new URL('../src-tauri/src/engine/openings.tsv', import.meta.url);
+
+const rows = ['eco\tname\tpgn'];
+for (const file of ['a', 'b', 'c', 'd', 'e']) {
+  const response = await fetch(`${BASE}/${file}.tsv`);
+  if (!response.ok) {
+    throw new Error(`could not download ${file}.tsv: HTTP ${response.status}`);
+  }
+  // Each file starts with the same header row.
+  const [, ...body] = (await response.text()).trim().split('\n');
+  rows.push(...body);
+}
+
+await writeFile(TARGET, `${rows.join('\n')}\n`);
+console.log(`wrote ${rows.length - 1} openings to ${TARGET.pathname}`);
@@ -2,7 +2,9 @@ use axum::{http::StatusCode, response::IntoResponse, Json};
 use serde::Serialize;
 use thiserror::Error;
 
-use crate::{config::store::ConfigError, engine::manager::EngineManagerError};
+use crate::{
+    api::types::AnalysisLine, config::store::ConfigError, engine::manager::EngineManagerError,
+};
 
 #[derive(Debug, Error)]
 pub enum ApiError {
@@ -17,7 +19,7 @@ pub enum ApiError {
 }
 
 #[derive(Debug, Serialize)]
-struct ErrorBody {
+pub struct ErrorBody {
     ok: bool,
     error: ErrorDetails,
 }
@@ -26,6 +28,9 @@ struct ErrorBody {
 struct ErrorDetails {
     code: &'static str,
     message: String,
+    /// Partial results of a cancelled analysis.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lines: Option<Vec<AnalysisLine>>,
 }
 
 impl ApiError {
@@ -33,11 +38,9 @@ impl ApiError {
         tracing::error!(%err, "internal api error");
         ApiError::Internal
     }
-}
 
-impl IntoResponse for ApiError {
-    fn into_response(self) -> axum::response::Response {
-        let (status, code, message) = match self {
+    fn into_parts(self) -> (StatusCode, &'static str, String) {
+        match self {
             ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, "bad_request", message),
             ApiError::Engine(err) => err.to_api_parts(),
             ApiError::Config(err) => (StatusCode::BAD_REQUEST, "config_error", err.to_string()),
@@ -46,15 +49,40 @@ impl IntoResponse for ApiError {
                 "internal_error",
                 "An unexpected error occurred.".to_string(),
             ),
+        }
+    }
+
+    /// The JSON body this error is sent as, for transports other than a plain response.
+    pub fn into_body(self) -> ErrorBody {
+        self.into_status_and_body().1
+    }
+
+    fn into_status_and_body(self) -> (StatusCode, ErrorBody) {
+        let lines = match &self {
+            ApiError::Engine(EngineManagerError::AnalysisCancelled { lines, .. }) => {
+                Some(lines.clone())
+            }
+            _ => None,
         };
+        let (status, code, message) = self.into_parts();
 
         (
             status,
-            Json(ErrorBody {
+            ErrorBody {
                 ok: false,
-                error: ErrorDetails { code, message },
-            }),
+                error: ErrorDetails {
+                    code,
+                    message,
+                    lines,
+                },
+            },
         )
-            .into_response()
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> axum::response::Response {
+        let (status, body) = self.into_status_and_body();
+        (status, Json(body)).into_response()
     }
 }
@@ -1,30 +1,69 @@
+use std::convert::Infallible;
+
 use axum::{
-    extract::State,
-    routing::{get, post},
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+    routing::{get, post, put},
     Json, Router,
 };
 use chrono::Utc;
+use serde::Serialize;
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio_stream::{wrappers::UnboundedReceiverStream, Stream};
 use uuid::Uuid;
 
 use crate::{
     api::{
         error::ApiError,
         types::{
-            AnalyzeRequest, DetectStockfishResponse, GenericOkResponse, HistoryItem,
-            StatusResponse, UpdateSettingsRequest,
+            AnalysisCacheResponse, AnalysisLine, AnalyzeRequest, AnalyzeResponse,
+            BatchAnalyzeRequest, BatchAnalyzeResponse, CancelAnalysisResponse,
+            DetectStockfishResponse, EngineOptionsResponse, EvaluateMoveRequest,
+            EvaluateMoveResponse, GenericOkResponse, HistoryItem, InfiniteAnalysisRequest,
+            InfiniteAnalysisResponse, JobResponse, PositionRequest, PositionResponse,
+            ReviewRequest, SetEngineProfileRequest, StatusResponse, TablebaseFoldersResponse,
+            TablebaseQuery, TablebaseResponse, UpdateSettingsRequest,
         },
     },
     app_state::AppState,
+    engine::manager::EngineManagerError,
 };
 
 pub fn api_routes() -> Router<AppState> {
     Router::new()
         .route("/status", get(status))
         .route("/analyze", post(analyze))
+        .route(
+            "/analyze/stream",
+            get(analyze_stream_query).post(analyze_stream_json),
+        )
+        .route("/analyze/batch", post(analyze_batch))
+        .route("/analyze/batch/stream", post(analyze_batch_stream))
         .route("/analyze/cancel", post(cancel_analysis))
+        .route(
+            "/analyze/{request_id}/cancel",
+            post(cancel_analysis_request),
+        )
+        .route(
+            "/analyze/infinite",
+            get(infinite_session).post(start_infinite),
+        )
+        .route("/analyze/infinite/position", put(set_infinite_position))
+        .route("/analyze/infinite/stop", post(stop_infinite))
+        .route("/jobs", post(submit_job))
+        .route("/jobs/{job_id}", get(job).delete(delete_job))
+        .route("/review", post(submit_review))
+        .route("/cache", get(analysis_cache).delete(clear_analysis_cache))
+        .route("/evaluate-move", post(evaluate_move))
+        .route("/position", post(position))
         .route("/history", get(history))
+        .route("/tablebase", get(tablebase))
+        .route("/tablebase/folders", get(tablebase_folders))
         .route("/settings", get(get_settings).put(update_settings))
         .route("/engine/restart", post(restart_engine))
+        .route("/engine/options", get(engine_options))
+        .route("/engine/profile", put(set_engine_profile))
         .route("/engine/detect", post(detect_stockfish))
         .route("/engine/choose", post(choose_stockfish))
         .route("/engine/download", post(download_stockfish))
@@ -43,35 +82,181 @@ async fn status(State(state): State<AppState>) -> Result<Json<StatusResponse>, A
 async fn analyze(
     State(state): State<AppState>,
     Json(request): Json<AnalyzeRequest>,
-) -> Result<Json<crate::api::types::AnalyzeResponse>, ApiError> {
-    let fen = request.fen.clone();
-    match state.engine.analyze(request).await {
+) -> Result<Json<AnalyzeResponse>, ApiError> {
+    analyze_and_record(&state, request, None).await.map(Json)
+}
+
+async fn analyze_stream_query(
+    State(state): State<AppState>,
+    Query(request): Query<AnalyzeRequest>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    analyze_stream(state, request)
+}
+
+async fn analyze_stream_json(
+    State(state): State<AppState>,
+    Json(request): Json<AnalyzeRequest>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    analyze_stream(state, request)
+}
+
+/// Streams an `info` event per scored engine line, then a single `result`
+/// event with the `AnalyzeResponse` or an `error` event with the usual error body.
+fn analyze_stream(
+    state: AppState,
+    mut request: AnalyzeRequest,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (events, receiver) = mpsc::unbounded_channel();
+    let request_id = request
+        .request_id
+        .get_or_insert_with(|| Uuid::new_v4().to_string())
+        .clone();
+
+    tokio::spawn(async move {
+        let (progress, mut lines) = mpsc::unbounded_channel::<AnalysisLine>();
+        let analysis = analyze_and_record(&state, request, Some(progress));
+        tokio::pin!(analysis);
+
+        let result = loop {
+            tokio::select! {
+                Some(line) = lines.recv() => {
+                    if events.send(Ok(json_event("info", &line))).is_ok() {
+                        continue;
+                    }
+                }
+                _ = events.closed() => {}
+                result = &mut analysis => break result,
+            }
+            // The client went away: stop the search and wait until it has
+            // let go of its worker.
+            let _ = state.engine.cancel_request(&request_id);
+            let _ = (&mut analysis).await;
+            return;
+        };
+
+        while let Ok(line) = lines.try_recv() {
+            let _ = events.send(Ok(json_event("info", &line)));
+        }
+
+        let final_event = match result {
+            Ok(response) => json_event("result", &response),
+            Err(err) => json_event("error", &err.into_body()),
+        };
+        let _ = events.send(Ok(final_event));
+    });
+
+    Sse::new(UnboundedReceiverStream::new(receiver)).keep_alive(KeepAlive::default())
+}
+
+/// Batches are left out of the history, which would otherwise fill with them.
+async fn analyze_batch(
+    State(state): State<AppState>,
+    Json(request): Json<BatchAnalyzeRequest>,
+) -> Result<Json<BatchAnalyzeResponse>, ApiError> {
+    Ok(Json(state.engine.analyze_batch(request, None).await?))
+}
+
+/// Streams an `item` event per position, in order, then a `done` event with
+/// the totals or an `error` event with the usual error body.
+async fn analyze_batch_stream(
+    State(state): State<AppState>,
+    Json(mut request): Json<BatchAnalyzeRequest>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (events, receiver) = mpsc::unbounded_channel();
+    let batch_id = request
+        .batch_id
+        .get_or_insert_with(|| Uuid::new_v4().to_string())
+        .clone();
+
+    tokio::spawn(async move {
+        let (items, mut ready) = mpsc::unbounded_channel();
+        let batch = state.engine.analyze_batch(request, Some(items));
+        tokio::pin!(batch);
+
+        let result = loop {
+            tokio::select! {
+                Some(item) = ready.recv() => {
+                    if events.send(Ok(json_event("item", &item))).is_ok() {
+                        continue;
+                    }
+                }
+                _ = events.closed() => {}
+                result = &mut batch => break result,
+            }
+            // The client went away: the batch's runners hold their own
+            // guards, so cancel it and wait until they let go of the workers.
+            let _ = state.engine.cancel_request(&batch_id);
+            let _ = (&mut batch).await;
+            return;
+        };
+        while let Ok(item) = ready.try_recv() {
+            let _ = events.send(Ok(json_event("item", &item)));
+        }
+
+        let final_event = match result {
+            Ok(response) => json_event("done", &response),
+            Err(err) => json_event("error", &ApiError::Engine(err).into_body()),
+        };
+        let _ = events.send(Ok(final_event));
+    });
+
+    Sse::new(UnboundedReceiverStream::new(receiver)).keep_alive(KeepAlive::default())
+}
+
+fn json_event(name: &'static str, data: &impl Serialize) -> Event {
+    Event::default()
+        .event(name)
+        .json_data(data)
+        .unwrap_or_else(|_| Event::default().event(name))
+}
+
+async fn analyze_and_record(
+    state: &AppState,
+    request: AnalyzeRequest,
+    progress: Option<UnboundedSender<AnalysisLine>>,
+) -> Result<AnalyzeResponse, ApiError> {
+    let requested_fen = request
+        .fen
+        .clone()
+        .or_else(|| request.start_fen.clone())
+        .unwrap_or_default();
+    let result = match progress {
+        Some(progress) => state.engine.analyze_with_progress(request, progress).await,
+        None => state.engine.analyze(request).await,
+    };
+    match result {
         Ok(response) => {
             state
                 .push_history(HistoryItem {
                     id: Uuid::new_v4(),
                     timestamp: Utc::now(),
-                    fen,
-                    best_move: Some(response.best_move.clone()),
+                    fen: response.fen.clone(),
+                    best_move: response.best_move.clone(),
                     difficulty: response.difficulty.clone(),
                     time_taken_ms: Some(response.time_taken_ms),
                     status: "ok".to_string(),
                     error: None,
+                    opening: response.opening.clone(),
                 })
                 .await;
-            Ok(Json(response))
+            Ok(response)
         }
         Err(err) => {
             state
                 .push_history(HistoryItem {
                     id: Uuid::new_v4(),
                     timestamp: Utc::now(),
-                    fen,
+                    fen: requested_fen,
                     best_move: None,
                     difficulty: None,
                     time_taken_ms: None,
-                    status: "error".to_string(),
+                    status: match err {
+                        EngineManagerError::AnalysisCancelled { .. } => "cancelled",
+                        _ => "error",
+                    }
+                    .to_string(),
                     error: Some(err.to_string()),
+                    opening: None,
                 })
                 .await;
             Err(ApiError::Engine(err))
@@ -79,16 +264,131 @@ async fn analyze(
     }
 }
 
-async fn cancel_analysis(State(state): State<AppState>) -> Json<GenericOkResponse> {
-    state.engine.cancel();
-    Json(GenericOkResponse {
+async fn cancel_analysis(State(state): State<AppState>) -> Json<CancelAnalysisResponse> {
+    let cancelled = state.engine.cancel();
+    let message = if cancelled.is_empty() {
+        "No analysis was running.".to_string()
+    } else {
+        "Cancellation requested. The running analysis will stop at the next UCI checkpoint."
+            .to_string()
+    };
+    Json(CancelAnalysisResponse {
         ok: true,
-        message:
-            "Cancellation requested. The running analysis will stop at the next UCI checkpoint."
-                .to_string(),
+        message,
+        cancelled,
     })
 }
 
+async fn cancel_analysis_request(
+    State(state): State<AppState>,
+    Path(request_id): Path<String>,
+) -> Result<Json<CancelAnalysisResponse>, ApiError> {
+    state.engine.cancel_request(&request_id)?;
+    Ok(Json(CancelAnalysisResponse {
+        ok: true,
+        message: format!("Cancellation requested for analysis {request_id}."),
+        cancelled: vec![request_id],
+    }))
+}
+
+async fn submit_job(
+    State(state): State<AppState>,
+    Json(request): Json<AnalyzeRequest>,
+) -> Result<(StatusCode, Json<JobResponse>), ApiError> {
+    let job = state.engine.submit_job(request)?;
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+async fn job(
+    State(state): State<AppState>,
+    Path(job_id): Path<String>,
+) -> Result<Json<JobResponse>, ApiError> {
+    Ok(Json(state.engine.job(&job_id)?))
+}
+
+async fn delete_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<String>,
+) -> Result<Json<JobResponse>, ApiError> {
+    Ok(Json(state.engine.delete_job(&job_id)?))
+}
+
+async fn submit_review(
+    State(state): State<AppState>,
+    Json(request): Json<ReviewRequest>,
+) -> Result<(StatusCode, Json<JobResponse>), ApiError> {
+    let job = state.engine.submit_review(request)?;
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+async fn analysis_cache(
+    State(state): State<AppState>,
+) -> Result<Json<AnalysisCacheResponse>, ApiError> {
+    Ok(Json(state.engine.analysis_cache().await?))
+}
+
+async fn clear_analysis_cache(
+    State(state): State<AppState>,
+) -> Result<Json<AnalysisCacheResponse>, ApiError> {
+    Ok(Json(state.engine.clear_analysis_cache().await?))
+}
+
+async fn start_infinite(
+    State(state): State<AppState>,
+    Json(request): Json<InfiniteAnalysisRequest>,
+) -> Result<Json<InfiniteAnalysisResponse>, ApiError> {
+    Ok(Json(state.engine.start_infinite(request).await?))
+}
+
+async fn infinite_session(
+    State(state): State<AppState>,
+) -> Result<Json<InfiniteAnalysisResponse>, ApiError> {
+    Ok(Json(state.engine.infinite_session()?))
+}
+
+async fn set_infinite_position(
+    State(state): State<AppState>,
+    Json(request): Json<InfiniteAnalysisRequest>,
+) -> Result<Json<InfiniteAnalysisResponse>, ApiError> {
+    Ok(Json(state.engine.set_infinite_position(request).await?))
+}
+
+async fn stop_infinite(
+    State(state): State<AppState>,
+) -> Result<Json<InfiniteAnalysisResponse>, ApiError> {
+    Ok(Json(state.engine.stop_infinite().await?))
+}
+
+async fn evaluate_move(
+    State(state): State<AppState>,
+    Json(request): Json<EvaluateMoveRequest>,
+) -> Result<Json<EvaluateMoveResponse>, ApiError> {
+    Ok(Json(state.engine.evaluate_move(request).await?))
+}
+
+async fn position(
+    State(state): State<AppState>,
+    Json(request): Json<PositionRequest>,
+) -> Result<Json<PositionResponse>, ApiError> {
+    Ok(Json(state.engine.position(request)?))
+}
+
+async fn tablebase(
+    State(state): State<AppState>,
+    Query(query): Query<TablebaseQuery>,
+) -> Result<Json<TablebaseResponse>, ApiError> {
+    let fen = query.fen.ok_or_else(|| {
+        EngineManagerError::InvalidRequest("the fen query parameter is required".to_string())
+    })?;
+    Ok(Json(state.engine.tablebase(&fen).await?))
+}
+
+async fn tablebase_folders(
+    State(state): State<AppState>,
+) -> Result<Json<TablebaseFoldersResponse>, ApiError> {
+    Ok(Json(state.engine.tablebase_folders().await?))
+}
+
 async fn history(State(state): State<AppState>) -> Json<Vec<HistoryItem>> {
     Json(state.history().await)
 }
@@ -122,6 +422,29 @@ async fn restart_engine(
     }))
 }
 
+async fn set_engine_profile(
+    State(state): State<AppState>,
+    Json(request): Json<SetEngineProfileRequest>,
+) -> Result<Json<GenericOkResponse>, ApiError> {
+    let message = match &request.profile {
+        Some(profile) => format!("Switched to engine profile {profile}."),
+        None => "Switched back to Stockfish.".to_string(),
+    };
+    state.engine.use_profile(request.profile).await?;
+    Ok(Json(GenericOkResponse { ok: true, message }))
+}
+
+async fn engine_options(
+    State(state): State<AppState>,
+) -> Result<Json<EngineOptionsResponse>, ApiError> {
+    let options = state.engine.engine_options()?;
+    Ok(Json(EngineOptionsResponse {
+        ok: true,
+        engine_name: state.engine.status().name,
+        options,
+    }))
+}
+
 async fn detect_stockfish(
     State(state): State<AppState>,
 ) -> Result<Json<DetectStockfishResponse>, ApiError> {
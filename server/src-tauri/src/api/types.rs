@@ -2,38 +2,267 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::{config::model::AppConfig, engine::difficulty::Difficulty};
+use crate::{
+    config::model::AppConfig,
+    engine::{
+        analysis::SearchTotals,
+        batch::BatchItem,
+        book::BookMove,
+        difficulty::Difficulty,
+        game_over::GameOver,
+        jobs::{JobKind, JobState},
+        notation::MoveDetail,
+        openings::Opening,
+        position_report::PositionReport,
+        review::{GameReview, MoveClassification},
+        tablebase::{TablebaseFolder, TablebaseProbe},
+        threat::Threat,
+        uci::{ScoreBound, UciOption, Wdl},
+    },
+};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct AnalyzeRequest {
-    pub fen: String,
+    /// The position to analyse. Optional when `moves` are given.
+    pub fen: Option<String>,
+    /// Position `moves` are played from. Defaults to the standard start position.
+    pub start_fen: Option<String>,
+    /// Game moves in UCI notation, played from `start_fen`.
+    #[serde(default)]
+    pub moves: Vec<String>,
     pub depth: Option<u32>,
     pub max_think_time_ms: Option<u64>,
     pub disregard_think_time: Option<bool>,
     pub request_id: Option<String>,
+    /// Overrides `analysis.book.enabled` for this request.
+    pub use_book: Option<bool>,
+    /// Set to `false` to search with the engine even when the Syzygy tables
+    /// cover the position.
+    pub use_tablebase: Option<bool>,
+    /// Also search what the opponent would play if the side to move passed.
+    pub include_threat: Option<bool>,
+    pub threat_depth: Option<u32>,
+    pub threat_think_time_ms: Option<u64>,
+    /// Set to `false` to skip `ucinewgame` and keep the engine's hash from
+    /// its previous search. Batches default to `false`.
+    pub new_game: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct AnalyzeResponse {
     pub ok: bool,
     pub request_id: String,
-    pub best_move: String,
+    /// The analysed position, after `moves` were played.
+    pub fen: String,
+    /// `None` only when the game is already over.
+    pub best_move: Option<String>,
     pub ponder: Option<String>,
+    pub best_move_detail: Option<MoveDetail>,
+    /// `ponder` as played after `best_move`.
+    pub ponder_detail: Option<MoveDetail>,
+    /// The position after `best_move` is played.
+    pub fen_after_best_move: Option<String>,
+    /// Set instead of searching when the position is checkmate, stalemate or a draw.
+    pub game_over: Option<GameOver>,
     pub depth: u32,
     pub time_taken_ms: u128,
     pub difficulty: Option<Difficulty>,
     pub lines: Vec<AnalysisLine>,
+    pub totals: SearchTotals,
     pub engine: EngineSummary,
+    /// The result came from the analysis cache rather than a new search.
+    pub cached: bool,
+    pub source: AnalysisSource,
+    /// Every move the opening book has for the position, when `source` is `book`.
+    pub book_moves: Option<Vec<BookMove>>,
+    /// The tablebase result and every move ranked, when `source` is `tablebase`.
+    pub tablebase: Option<TablebaseProbe>,
+    /// Set when `include_threat` was requested, the side to move is not in
+    /// check and the threat search succeeded.
+    pub threat: Option<Threat>,
+    /// The opening reached, or the last one passed through on the way there.
+    pub opening: Option<Opening>,
+    /// Material, hanging pieces, checks and the like, read off the board.
+    pub position_report: PositionReport,
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// Where an `AnalyzeResponse` came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalysisSource {
+    Engine,
+    Cache,
+    Book,
+    Tablebase,
+    /// The game was already over, so nothing was searched.
+    GameOver,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AnalysisLine {
     pub rank: u8,
     pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
     pub move_uci: Option<String>,
     pub score_cp: Option<i32>,
     pub mate: Option<i32>,
+    /// Set when the score is only a fail-high/fail-low bound.
+    pub bound: Option<ScoreBound>,
+    /// Reported by the engine, or estimated from the score when it has none.
+    pub wdl: Option<Wdl>,
+    /// `score_cp`, `mate` and `wdl` from White's point of view.
+    pub white_score_cp: Option<i32>,
+    pub white_mate: Option<i32>,
+    pub white_wdl: Option<Wdl>,
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    pub time_ms: Option<u64>,
+    pub hashfull: Option<u32>,
+    pub tbhits: Option<u64>,
     pub pv: Vec<String>,
+    pub move_san: Option<String>,
+    /// The first `pv` move in detail.
+    pub move_detail: Option<MoveDetail>,
+    /// `pv` in SAN, up to the first illegal move.
+    #[serde(default)]
+    pub pv_san: Vec<String>,
+    /// `pv_san` with move numbers, such as `12... Nxe4 13. Bxe4 d5`.
+    pub pv_text: Option<String>,
+    /// Index into `pv` of the first move that is not legal when replayed.
+    pub pv_illegal_index: Option<usize>,
+}
+
+/// Many positions analysed in one call, given as requests or as an EPD text.
+/// The other fields apply to every position that does not set them itself.
+#[derive(Debug, Deserialize)]
+pub struct BatchAnalyzeRequest {
+    #[serde(default)]
+    pub positions: Vec<AnalyzeRequest>,
+    /// One position per line; an `id` operation becomes the item's `id`.
+    pub epd: Option<String>,
+    /// Cancels the whole batch through `/analyze/{batch_id}/cancel`.
+    pub batch_id: Option<String>,
+    pub depth: Option<u32>,
+    pub max_think_time_ms: Option<u64>,
+    pub disregard_think_time: Option<bool>,
+    pub use_book: Option<bool>,
+    pub use_tablebase: Option<bool>,
+    pub include_threat: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchAnalyzeResponse {
+    pub ok: bool,
+    pub batch_id: String,
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub time_taken_ms: u128,
+    /// In the order the positions were sent. Empty on the stream, which
+    /// sends each item as it is ready.
+    pub results: Vec<BatchItem>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EvaluateMoveRequest {
+    pub fen: Option<String>,
+    pub start_fen: Option<String>,
+    #[serde(default)]
+    pub moves: Vec<String>,
+    /// The move to evaluate, in UCI or SAN.
+    #[serde(rename = "move")]
+    pub candidate: String,
+    pub depth: Option<u32>,
+    pub max_think_time_ms: Option<u64>,
+    pub disregard_think_time: Option<bool>,
+    pub request_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EvaluateMoveResponse {
+    pub ok: bool,
+    pub request_id: String,
+    pub fen: String,
+    pub move_uci: String,
+    pub move_san: String,
+    pub move_detail: MoveDetail,
+    pub best_move: Option<String>,
+    pub best_move_san: Option<String>,
+    /// Scores for the side to move: the best line's, and the move's.
+    pub best_score_cp: Option<i32>,
+    pub best_mate: Option<i32>,
+    pub move_score_cp: Option<i32>,
+    pub move_mate: Option<i32>,
+    /// Capped like a game review's, and 0 when the move is the best move.
+    pub centipawn_loss: u32,
+    pub classification: MoveClassification,
+    /// The opponent's best answer to the move and the line after it.
+    pub refutation: Vec<String>,
+    pub refutation_san: Vec<String>,
+    /// The top line of the full search.
+    pub best_line: Option<AnalysisLine>,
+    /// The line of the search restricted to the move; it starts with the move.
+    pub move_line: Option<AnalysisLine>,
+    pub depth: u32,
+    /// Both searches together.
+    pub time_taken_ms: u128,
+    pub engine: EngineSummary,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PositionRequest {
+    pub fen: Option<String>,
+    pub start_fen: Option<String>,
+    #[serde(default)]
+    pub moves: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionResponse {
+    pub ok: bool,
+    pub fen: String,
+    pub game_over: Option<GameOver>,
+    pub opening: Option<Opening>,
+    pub position_report: PositionReport,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TablebaseQuery {
+    pub fen: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TablebaseResponse {
+    pub ok: bool,
+    pub fen: String,
+    #[serde(flatten)]
+    pub probe: TablebaseProbe,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TablebaseFoldersResponse {
+    pub ok: bool,
+    /// Most pieces of any table that could be loaded; 0 when there are none.
+    pub max_pieces: usize,
+    pub folders: Vec<TablebaseFolder>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InfiniteAnalysisRequest {
+    pub fen: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InfiniteAnalysisResponse {
+    pub ok: bool,
+    pub session_id: String,
+    pub fen: String,
+    pub running: bool,
+    pub elapsed_ms: u128,
+    pub depth: Option<u32>,
+    pub lines: Vec<AnalysisLine>,
+    pub best_move: Option<String>,
+    pub ponder: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -55,8 +284,24 @@ pub struct EngineStatusResponse {
     pub status: String,
     pub name: Option<String>,
     pub stockfish_path: Option<String>,
+    /// Active engine profile, or `None` for the default Stockfish.
+    pub profile: Option<String>,
     pub last_error: Option<String>,
     pub current_job_id: Option<String>,
+    /// Automatic restarts across all workers.
+    pub restart_count: u32,
+    pub last_crash_reason: Option<String>,
+    /// One entry per engine process in the pool; the first is the primary.
+    pub workers: Vec<WorkerStatusResponse>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkerStatusResponse {
+    pub index: usize,
+    pub status: String,
+    pub current_job_id: Option<String>,
+    pub restart_count: u32,
+    pub last_error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -69,6 +314,7 @@ pub struct HistoryItem {
     pub time_taken_ms: Option<u128>,
     pub status: String,
     pub error: Option<String>,
+    pub opening: Option<Opening>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -77,12 +323,110 @@ pub struct UpdateSettingsRequest {
     pub restart_engine: Option<bool>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct SetEngineProfileRequest {
+    /// Profile name, or `null` to go back to Stockfish.
+    pub profile: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct GenericOkResponse {
     pub ok: bool,
     pub message: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ReviewRequest {
+    /// A single game in PGN. A `FEN` tag sets its start position.
+    pub pgn: Option<String>,
+    /// Used with `moves` instead of `pgn`. Defaults to the standard start position.
+    pub start_fen: Option<String>,
+    /// Game moves in UCI notation, played from `start_fen`.
+    #[serde(default)]
+    pub moves: Vec<String>,
+    /// Search depth for every position of the game.
+    pub depth: Option<u32>,
+    pub max_think_time_ms: Option<u64>,
+    pub disregard_think_time: Option<bool>,
+    /// Becomes the job ID; generated when missing.
+    pub review_id: Option<String>,
+}
+
+/// An analysis submitted through `POST /jobs`, or a `POST /review` game review.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobResponse {
+    pub ok: bool,
+    pub job_id: String,
+    pub kind: JobKind,
+    pub state: JobState,
+    pub submitted_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub progress: JobProgress,
+    /// Latest line per MultiPV rank, including partial lines while running.
+    /// Always empty for reviews.
+    pub lines: Vec<AnalysisLine>,
+    /// The full `/analyze` response, or the game review, once the job completed.
+    pub result: Option<JobResult>,
+    pub error: Option<JobError>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum JobResult {
+    Analysis(Box<AnalyzeResponse>),
+    Review(Box<GameReview>),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobProgress {
+    pub depth: Option<u32>,
+    pub target_depth: u32,
+    pub elapsed_ms: u128,
+    /// Positions of a reviewed game analysed so far; `None` for analyses.
+    pub plies_analysed: Option<usize>,
+    pub plies_total: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobError {
+    pub code: &'static str,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CancelAnalysisResponse {
+    pub ok: bool,
+    pub message: String,
+    /// Request IDs of the analyses that were asked to stop.
+    pub cancelled: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisCacheResponse {
+    pub ok: bool,
+    pub memory_entries: usize,
+    pub disk: DiskCacheResponse,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskCacheResponse {
+    pub enabled: bool,
+    pub path: String,
+    pub entries: usize,
+    pub size_bytes: u64,
+    pub max_bytes: u64,
+    pub min_depth: u32,
+    /// Unreadable records skipped since the cache was opened or compacted.
+    pub skipped_records: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EngineOptionsResponse {
+    pub ok: bool,
+    pub engine_name: Option<String>,
+    pub options: Vec<UciOption>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct DetectStockfishResponse {
     pub ok: bool,
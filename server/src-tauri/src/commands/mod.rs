@@ -2,7 +2,10 @@ use serde::{Deserialize, Serialize};
 use tauri::State;
 
 use crate::{
-    api::types::{DetectStockfishResponse, EngineStatusResponse, GenericOkResponse, HistoryItem},
+    api::types::{
+        AnalysisCacheResponse, CancelAnalysisResponse, DetectStockfishResponse,
+        EngineOptionsResponse, EngineStatusResponse, GenericOkResponse, HistoryItem,
+    },
     app_state::AppState,
     config::model::{AppConfig, BotTimingPreset},
 };
@@ -51,6 +54,11 @@ pub async fn get_ui_status(state: State<'_, AppState>) -> Result<UiStatusRespons
             "Thinking".to_string(),
             "A move request is currently being calculated.".to_string(),
         ),
+        "infinite_analysis" => (
+            "Analysing".to_string(),
+            "An infinite analysis session is running. Move requests are refused until it stops."
+                .to_string(),
+        ),
         "starting" => (
             "Starting".to_string(),
             "The chess engine is being prepared.".to_string(),
@@ -174,14 +182,85 @@ pub async fn restart_engine(state: State<'_, AppState>) -> Result<GenericOkRespo
 }
 
 #[tauri::command]
-pub async fn cancel_analysis(state: State<'_, AppState>) -> Result<GenericOkResponse, String> {
-    state.engine.cancel();
-    Ok(GenericOkResponse {
+pub async fn set_engine_profile(
+    state: State<'_, AppState>,
+    profile: Option<String>,
+) -> Result<GenericOkResponse, String> {
+    match state.engine.use_profile(profile).await {
+        Ok(()) => Ok(GenericOkResponse {
+            ok: true,
+            message: "Engine profile switched.".to_string(),
+        }),
+        Err(err) => Ok(GenericOkResponse {
+            ok: false,
+            message: format!("Could not switch engine profile: {err}"),
+        }),
+    }
+}
+
+#[tauri::command]
+pub async fn get_engine_options(
+    state: State<'_, AppState>,
+) -> Result<EngineOptionsResponse, String> {
+    let options = state
+        .engine
+        .engine_options()
+        .map_err(|err| err.to_string())?;
+    Ok(EngineOptionsResponse {
+        ok: true,
+        engine_name: state.engine.status().name,
+        options,
+    })
+}
+
+#[tauri::command]
+pub async fn cancel_analysis(state: State<'_, AppState>) -> Result<CancelAnalysisResponse, String> {
+    let cancelled = state.engine.cancel();
+    Ok(CancelAnalysisResponse {
+        ok: true,
+        message: "Cancellation requested.".to_string(),
+        cancelled,
+    })
+}
+
+#[tauri::command]
+pub async fn cancel_analysis_request(
+    state: State<'_, AppState>,
+    request_id: String,
+) -> Result<CancelAnalysisResponse, String> {
+    state
+        .engine
+        .cancel_request(&request_id)
+        .map_err(|err| err.to_string())?;
+    Ok(CancelAnalysisResponse {
         ok: true,
         message: "Cancellation requested.".to_string(),
+        cancelled: vec![request_id],
     })
 }
 
+#[tauri::command]
+pub async fn get_analysis_cache(
+    state: State<'_, AppState>,
+) -> Result<AnalysisCacheResponse, String> {
+    state
+        .engine
+        .analysis_cache()
+        .await
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+pub async fn clear_analysis_cache(
+    state: State<'_, AppState>,
+) -> Result<AnalysisCacheResponse, String> {
+    state
+        .engine
+        .clear_analysis_cache()
+        .await
+        .map_err(|err| err.to_string())
+}
+
 #[tauri::command]
 pub async fn detect_stockfish(
     state: State<'_, AppState>,
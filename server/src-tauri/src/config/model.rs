@@ -1,4 +1,7 @@
-use std::net::{IpAddr, Ipv4Addr};
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, Ipv4Addr},
+};
 
 use serde::{Deserialize, Serialize};
 
@@ -23,6 +26,33 @@ pub struct EngineConfig {
     pub syzygy_paths: Vec<String>,
     pub multipv: u8,
     pub auto_restart: bool,
+    #[serde(default = "default_retry_after_crash")]
+    pub retry_after_crash: bool,
+    /// Number of engine processes; `hash_mb` and `threads` are split between them.
+    #[serde(default = "default_workers")]
+    pub workers: u8,
+    /// Any other UCI options, by name, checked against the engine's own
+    /// option list before they are sent.
+    #[serde(default)]
+    pub extra_options: BTreeMap<String, String>,
+    /// Named launch setups for other UCI engines (Lc0, Berserk, ...).
+    #[serde(default)]
+    pub profiles: BTreeMap<String, EngineProfile>,
+    /// Profile to run instead of `stockfish_path`; `None` runs Stockfish.
+    #[serde(default)]
+    pub active_profile: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EngineProfile {
+    pub path: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    /// UCI options for this engine, applied on top of `extra_options`.
+    #[serde(default)]
+    pub options: BTreeMap<String, String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -34,6 +64,48 @@ pub struct AnalysisConfig {
     pub max_delay_ms: u64,
     #[serde(default = "default_timing_preset")]
     pub timing_preset: BotTimingPreset,
+    /// How long a request waits for an idle engine worker before `engine_busy`.
+    #[serde(default = "default_queue_timeout_ms")]
+    pub queue_timeout_ms: u64,
+    /// How long a finished `/jobs` result stays available.
+    #[serde(default = "default_job_retention_secs")]
+    pub job_retention_secs: u64,
+    /// Finished analyses kept in memory for repeated positions; 0 disables the cache.
+    #[serde(default = "default_cache_size")]
+    pub cache_size: usize,
+    /// Size cap of the on-disk analysis cache in megabytes; 0 disables it.
+    #[serde(default = "default_disk_cache_max_mb")]
+    pub disk_cache_max_mb: u64,
+    /// Only analyses at least this deep are written to the on-disk cache.
+    #[serde(default = "default_disk_cache_min_depth")]
+    pub disk_cache_min_depth: u32,
+    #[serde(default)]
+    pub book: BookConfig,
+}
+
+/// Polyglot opening books, probed before the engine is asked.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BookConfig {
+    /// Whether analyses use the books unless a request sets `use_book`.
+    pub enabled: bool,
+    /// Polyglot `.bin` files, probed in order.
+    pub paths: Vec<String>,
+    /// Book moves weighing less than this are ignored.
+    pub min_weight: u16,
+    /// Books are only probed during the first `max_ply` half-moves of a game.
+    pub max_ply: u32,
+}
+
+impl Default for BookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            paths: Vec::new(),
+            min_weight: 1,
+            max_ply: 24,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -83,6 +155,11 @@ impl Default for AppConfig {
                 syzygy_paths: Vec::new(),
                 multipv: 4,
                 auto_restart: true,
+                retry_after_crash: true,
+                workers: default_workers(),
+                extra_options: BTreeMap::new(),
+                profiles: BTreeMap::new(),
+                active_profile: None,
             },
             analysis: AnalysisConfig {
                 difficulty_enabled: true,
@@ -91,6 +168,12 @@ impl Default for AppConfig {
                 min_delay_ms,
                 max_delay_ms,
                 timing_preset,
+                queue_timeout_ms: default_queue_timeout_ms(),
+                job_retention_secs: default_job_retention_secs(),
+                cache_size: default_cache_size(),
+                disk_cache_max_mb: default_disk_cache_max_mb(),
+                disk_cache_min_depth: default_disk_cache_min_depth(),
+                book: BookConfig::default(),
             },
         }
     }
@@ -114,6 +197,63 @@ impl AppConfig {
         if self.engine.multipv == 0 || self.engine.multipv > 8 {
             return Err("multipv must be between 1 and 8".to_string());
         }
+        if self.engine.workers == 0 || self.engine.workers > 8 {
+            return Err("workers must be between 1 and 8".to_string());
+        }
+        if self.analysis.queue_timeout_ms > 600_000 {
+            return Err("queue_timeout_ms must be at most 600000".to_string());
+        }
+        if self.analysis.job_retention_secs > 86_400 {
+            return Err("job_retention_secs must be at most 86400".to_string());
+        }
+        if self.analysis.cache_size > 100_000 {
+            return Err("cache_size must be at most 100000".to_string());
+        }
+        if self.analysis.disk_cache_max_mb > 4_096 {
+            return Err("disk_cache_max_mb must be at most 4096".to_string());
+        }
+        if !(1..=40).contains(&self.analysis.disk_cache_min_depth) {
+            return Err("disk_cache_min_depth must be between 1 and 40".to_string());
+        }
+        if self
+            .analysis
+            .book
+            .paths
+            .iter()
+            .any(|path| path.trim().is_empty())
+        {
+            return Err("book paths must not be empty".to_string());
+        }
+        if !(1..=200).contains(&self.analysis.book.max_ply) {
+            return Err("book max_ply must be between 1 and 200".to_string());
+        }
+        if let Some(name) = self
+            .engine
+            .extra_options
+            .keys()
+            .find(|name| is_managed_option(name))
+        {
+            return Err(format!(
+                "{name} is managed by its own engine setting and cannot be set in extra_options"
+            ));
+        }
+        for (profile_name, profile) in &self.engine.profiles {
+            if profile.path.trim().is_empty() {
+                return Err(format!("engine profile {profile_name} needs a path"));
+            }
+            if let Some(name) = profile.options.keys().find(|name| is_managed_option(name)) {
+                return Err(format!(
+                    "{name} is managed by its own engine setting and cannot be set in engine profile {profile_name}"
+                ));
+            }
+        }
+        if let Some(active) = &self.engine.active_profile {
+            if !self.engine.profiles.contains_key(active) {
+                return Err(format!(
+                    "active_profile {active} is not a configured engine profile"
+                ));
+            }
+        }
         if self.analysis.candidate_threshold_cp < 0 || self.analysis.candidate_threshold_cp > 500 {
             return Err("candidate_threshold_cp must be between 0 and 500".to_string());
         }
@@ -123,6 +263,22 @@ impl AppConfig {
         Ok(())
     }
 
+    pub fn active_profile(&self) -> Option<&EngineProfile> {
+        self.engine
+            .active_profile
+            .as_ref()
+            .and_then(|name| self.engine.profiles.get(name))
+    }
+
+    /// `extra_options` merged with the active profile's options.
+    pub fn uci_options(&self) -> BTreeMap<String, String> {
+        let mut options = self.engine.extra_options.clone();
+        if let Some(profile) = self.active_profile() {
+            options.extend(profile.options.clone());
+        }
+        options
+    }
+
     pub fn apply_timing_preset(&mut self, preset: BotTimingPreset) {
         let (min_delay_ms, max_delay_ms) = preset.delay_bounds();
         self.analysis.timing_preset = preset;
@@ -131,10 +287,48 @@ impl AppConfig {
     }
 }
 
+/// UCI options the server always sets from dedicated config fields.
+const MANAGED_ENGINE_OPTIONS: [&str; 5] =
+    ["Hash", "Threads", "MultiPV", "SyzygyPath", "UCI_ShowWDL"];
+
+fn is_managed_option(name: &str) -> bool {
+    MANAGED_ENGINE_OPTIONS
+        .iter()
+        .any(|managed| managed.eq_ignore_ascii_case(name))
+}
+
 fn default_timing_preset() -> BotTimingPreset {
     BotTimingPreset::Balanced
 }
 
+fn default_retry_after_crash() -> bool {
+    true
+}
+
+fn default_workers() -> u8 {
+    1
+}
+
+fn default_queue_timeout_ms() -> u64 {
+    5_000
+}
+
+fn default_job_retention_secs() -> u64 {
+    600
+}
+
+fn default_cache_size() -> usize {
+    256
+}
+
+fn default_disk_cache_max_mb() -> u64 {
+    64
+}
+
+fn default_disk_cache_min_depth() -> u32 {
+    20
+}
+
 pub fn recommended_hash_mb() -> u32 {
     256
 }
@@ -0,0 +1,643 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::{mpsc as tokio_mpsc, oneshot, watch};
+
+use crate::{
+    api::types::AnalysisLine,
+    config::model::AppConfig,
+    engine::{
+        analysis::{AnalysisAccumulator, RawAnalysisResult},
+        manager::{apply_options_to_process, EngineManagerError, EngineStatus},
+        stockfish::{EngineLaunch, SearchLimits, StockfishError, StockfishProcess},
+        uci::{
+            info_to_analysis_line, parse_bestmove_line, parse_info_line, UciOption, UciPosition,
+        },
+    },
+};
+
+/// How often the idle actor checks whether its engine process has died.
+const CRASH_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// How long an infinite search waits for engine output before checking for commands.
+const INFINITE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Everything the dashboard and `/status` need to know about the engine.
+///
+/// The actor thread is the only writer for process-related fields; readers
+/// clone it out of the watch channel and never wait on a running search.
+#[derive(Debug, Clone)]
+pub struct EngineSnapshot {
+    pub status: EngineStatus,
+    pub has_process: bool,
+    /// How the current (or last attempted) engine was started.
+    pub launch: Option<EngineLaunch>,
+    /// The engine profile that launch came from, if any.
+    pub profile: Option<String>,
+    pub name: Option<String>,
+    pub last_error: Option<String>,
+    pub current_job_id: Option<String>,
+    pub restart_count: u32,
+    pub last_crash_reason: Option<String>,
+    /// Option catalogue from the running engine's handshake.
+    pub options: Vec<UciOption>,
+}
+
+impl EngineSnapshot {
+    /// The status a live engine should report once a start/restart settles.
+    pub fn running_status(&self) -> EngineStatus {
+        if self.current_job_id.is_some() {
+            EngineStatus::Analyzing
+        } else {
+            EngineStatus::Ready
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchParams {
+    pub request_id: String,
+    pub position: UciPosition,
+    pub depth: u32,
+    pub max_think_time_ms: u64,
+    pub disregard_think_time: bool,
+    /// Send `ucinewgame` before searching, clearing the engine's hash.
+    pub new_game: bool,
+    /// Only these UCI moves are searched at the root; all of them when empty.
+    pub searchmoves: Vec<String>,
+    /// Set to stop this search early.
+    pub cancel: Arc<AtomicBool>,
+    /// Receives every scored `info` line while the search runs.
+    pub progress: Option<tokio_mpsc::UnboundedSender<AnalysisLine>>,
+}
+
+/// A `go infinite` search started through the infinite analysis endpoints.
+#[derive(Debug, Clone)]
+pub struct InfiniteSession {
+    pub session_id: String,
+    pub fen: String,
+    pub started_at: Instant,
+    pub stopped_at: Option<Instant>,
+    /// Latest line per MultiPV rank for the current position.
+    pub lines: Vec<AnalysisLine>,
+    pub best_move: Option<String>,
+    pub ponder: Option<String>,
+}
+
+pub struct SearchOutcome {
+    pub raw: RawAnalysisResult,
+    pub time_taken_ms: u128,
+    pub engine_name: Option<String>,
+    pub engine_status: String,
+}
+
+pub enum EngineCommand {
+    Analyze {
+        params: SearchParams,
+        reply: oneshot::Sender<Result<SearchOutcome, EngineManagerError>>,
+    },
+    ApplyOptions {
+        config: AppConfig,
+        reply: oneshot::Sender<Result<(), EngineManagerError>>,
+    },
+    /// Replaces the running process (if any) with an already started one.
+    Install {
+        process: StockfishProcess,
+        launch: EngineLaunch,
+        profile: Option<String>,
+        reply: oneshot::Sender<()>,
+    },
+    StartInfinite {
+        session_id: String,
+        fen: String,
+        reply: oneshot::Sender<Result<InfiniteSession, EngineManagerError>>,
+    },
+    SetInfinitePosition {
+        fen: String,
+        reply: oneshot::Sender<Result<InfiniteSession, EngineManagerError>>,
+    },
+    StopInfinite {
+        reply: oneshot::Sender<Result<InfiniteSession, EngineManagerError>>,
+    },
+}
+
+/// Handle to the thread that owns the `StockfishProcess`.
+///
+/// Commands are processed one at a time; the thread exits once every handle
+/// has been dropped.
+#[derive(Clone)]
+pub struct EngineActor {
+    commands: mpsc::Sender<EngineCommand>,
+    snapshot: Arc<watch::Sender<EngineSnapshot>>,
+    session: Arc<watch::Sender<Option<InfiniteSession>>>,
+}
+
+impl EngineActor {
+    pub fn spawn() -> Self {
+        let (commands, receiver) = mpsc::channel();
+        let snapshot = Arc::new(watch::Sender::new(EngineSnapshot {
+            status: EngineStatus::NotConfigured,
+            has_process: false,
+            launch: None,
+            profile: None,
+            name: None,
+            last_error: None,
+            current_job_id: None,
+            restart_count: 0,
+            last_crash_reason: None,
+            options: Vec::new(),
+        }));
+
+        let session = Arc::new(watch::Sender::new(None));
+
+        let actor = Actor {
+            process: None,
+            snapshot: snapshot.clone(),
+            session: session.clone(),
+        };
+        thread::Builder::new()
+            .name("engine-actor".to_string())
+            .spawn(move || actor.run(receiver))
+            .expect("failed to spawn engine actor thread");
+
+        Self {
+            commands,
+            snapshot,
+            session,
+        }
+    }
+
+    pub async fn request<T>(
+        &self,
+        command: impl FnOnce(oneshot::Sender<T>) -> EngineCommand,
+    ) -> Result<T, EngineManagerError> {
+        let (reply, response) = oneshot::channel();
+        self.commands
+            .send(command(reply))
+            .map_err(|_| EngineManagerError::WorkerStopped)?;
+        response
+            .await
+            .map_err(|_| EngineManagerError::WorkerStopped)
+    }
+
+    pub fn snapshot(&self) -> EngineSnapshot {
+        self.snapshot.borrow().clone()
+    }
+
+    pub fn update(&self, modify: impl FnOnce(&mut EngineSnapshot)) {
+        self.snapshot.send_modify(modify);
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<EngineSnapshot> {
+        self.snapshot.subscribe()
+    }
+
+    /// The current or most recently stopped infinite analysis session.
+    pub fn infinite_session(&self) -> Option<InfiniteSession> {
+        self.session.borrow().clone()
+    }
+}
+
+struct Actor {
+    process: Option<StockfishProcess>,
+    snapshot: Arc<watch::Sender<EngineSnapshot>>,
+    session: Arc<watch::Sender<Option<InfiniteSession>>>,
+}
+
+impl Actor {
+    fn run(mut self, commands: mpsc::Receiver<EngineCommand>) {
+        let mut pending = None;
+
+        loop {
+            let next = match pending.take() {
+                Some(command) => Ok(command),
+                None => commands.recv_timeout(CRASH_POLL_INTERVAL),
+            };
+
+            match next {
+                Ok(EngineCommand::Analyze { params, reply }) => {
+                    let _ = reply.send(self.analyze(params));
+                }
+                Ok(EngineCommand::ApplyOptions { config, reply }) => {
+                    let result = match self.process.as_mut() {
+                        Some(engine) => apply_options_to_process(engine, &config)
+                            .map_err(EngineManagerError::from),
+                        None => Err(EngineManagerError::NotConfigured),
+                    };
+                    let _ = reply.send(result);
+                }
+                Ok(EngineCommand::Install {
+                    process,
+                    launch,
+                    profile,
+                    reply,
+                }) => {
+                    self.install(process, launch, profile);
+                    let _ = reply.send(());
+                }
+                Ok(EngineCommand::StartInfinite {
+                    session_id,
+                    fen,
+                    reply,
+                }) => match self.start_infinite(session_id, fen) {
+                    Ok(session) => {
+                        let _ = reply.send(Ok(session));
+                        pending = self.run_infinite(&commands);
+                    }
+                    Err(err) => {
+                        let _ = reply.send(Err(err));
+                    }
+                },
+                Ok(EngineCommand::SetInfinitePosition { reply, .. })
+                | Ok(EngineCommand::StopInfinite { reply }) => {
+                    let _ = reply.send(Err(EngineManagerError::NoAnalysisSession));
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    if self
+                        .process
+                        .as_mut()
+                        .is_some_and(|engine| engine.has_exited())
+                    {
+                        self.crashed("Engine process exited while idle.".to_string());
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    }
+
+    fn install(
+        &mut self,
+        process: StockfishProcess,
+        launch: EngineLaunch,
+        profile: Option<String>,
+    ) {
+        let name = process.name.clone();
+        let options = process.options().to_vec();
+        self.process = Some(process);
+        self.snapshot.send_modify(|s| {
+            s.has_process = true;
+            s.launch = Some(launch);
+            s.profile = profile;
+            s.name = name;
+            s.options = options;
+            s.status = EngineStatus::Ready;
+            s.last_error = None;
+            s.current_job_id = None;
+        });
+    }
+
+    fn engine(&mut self) -> Result<&mut StockfishProcess, EngineManagerError> {
+        let missing = self.missing_engine();
+        self.process.as_mut().ok_or(missing)
+    }
+
+    fn missing_engine(&self) -> EngineManagerError {
+        match self.snapshot.borrow().status {
+            EngineStatus::Crashed => EngineManagerError::Stockfish(StockfishError::EngineExited),
+            _ => EngineManagerError::NotConfigured,
+        }
+    }
+
+    fn analyze(&mut self, params: SearchParams) -> Result<SearchOutcome, EngineManagerError> {
+        let Some(engine) = self.process.as_mut() else {
+            return Err(self.missing_engine());
+        };
+
+        self.snapshot.send_modify(|s| {
+            s.current_job_id = Some(params.request_id.clone());
+            s.status = EngineStatus::Analyzing;
+            s.last_error = None;
+        });
+
+        let started = Instant::now();
+        let prepared = if params.new_game {
+            engine.new_game()
+        } else {
+            engine.sync()
+        };
+        let raw = prepared.and_then(|()| {
+            engine.analyze(
+                &params.position,
+                SearchLimits {
+                    depth: params.depth,
+                    max_think_time_ms: params.max_think_time_ms,
+                    disregard_think_time: params.disregard_think_time,
+                    searchmoves: &params.searchmoves,
+                },
+                || params.cancel.load(Ordering::SeqCst),
+                |line| {
+                    if let Some(progress) = &params.progress {
+                        let _ = progress.send(line.clone());
+                    }
+                },
+            )
+        });
+        let time_taken_ms = started.elapsed().as_millis();
+        let exited = engine.has_exited();
+
+        match raw {
+            Ok(raw) => {
+                self.snapshot.send_modify(|s| {
+                    s.status = EngineStatus::Ready;
+                    s.current_job_id = None;
+                });
+                let current = self.snapshot.borrow();
+                Ok(SearchOutcome {
+                    raw,
+                    time_taken_ms,
+                    engine_name: current.name.clone(),
+                    engine_status: current.status.as_str().to_string(),
+                })
+            }
+            Err(err) if exited || matches!(err, StockfishError::EngineExited) => {
+                self.crashed(format!("Engine process exited during analysis: {err}"));
+                Err(EngineManagerError::Stockfish(StockfishError::EngineExited))
+            }
+            Err(err) => {
+                self.snapshot.send_modify(|s| {
+                    s.status = EngineStatus::Error;
+                    s.current_job_id = None;
+                    s.last_error = Some(err.to_string());
+                });
+                Err(EngineManagerError::Stockfish(err))
+            }
+        }
+    }
+
+    fn start_infinite(
+        &mut self,
+        session_id: String,
+        fen: String,
+    ) -> Result<InfiniteSession, EngineManagerError> {
+        let engine = self.engine()?;
+        if let Err(err) = engine.new_game().and_then(|()| engine.go_infinite(&fen)) {
+            return Err(self.search_failed(err));
+        }
+
+        let session = InfiniteSession {
+            session_id: session_id.clone(),
+            fen,
+            started_at: Instant::now(),
+            stopped_at: None,
+            lines: Vec::new(),
+            best_move: None,
+            ponder: None,
+        };
+        self.session.send_replace(Some(session.clone()));
+        self.snapshot.send_modify(|s| {
+            s.current_job_id = Some(session_id);
+            s.status = EngineStatus::InfiniteAnalysis;
+            s.last_error = None;
+        });
+        Ok(session)
+    }
+
+    /// Keeps a `go infinite` search running while serving commands.
+    ///
+    /// One-shot analysis is refused while the session is active. Returns a
+    /// command that ended the session and still has to be handled by the main loop.
+    fn run_infinite(&mut self, commands: &mpsc::Receiver<EngineCommand>) -> Option<EngineCommand> {
+        let mut accumulator = self.session_accumulator();
+        let mut searching = true;
+
+        loop {
+            match commands.try_recv() {
+                Ok(EngineCommand::Analyze { reply, .. }) => {
+                    let _ = reply.send(Err(EngineManagerError::EngineBusy(
+                        "An infinite analysis session is running. Stop it before requesting a move."
+                            .to_string(),
+                    )));
+                }
+                Ok(EngineCommand::StartInfinite { reply, .. }) => {
+                    let _ = reply.send(Err(EngineManagerError::EngineBusy(
+                        "An infinite analysis session is already running.".to_string(),
+                    )));
+                }
+                Ok(EngineCommand::StopInfinite { reply }) => {
+                    let result = self
+                        .stop_infinite(&mut accumulator, searching)
+                        .map(|()| self.finish_session());
+                    let _ = reply.send(result);
+                    return None;
+                }
+                Ok(EngineCommand::SetInfinitePosition { fen, reply }) => {
+                    let result = self
+                        .stop_infinite(&mut accumulator, searching)
+                        .and_then(|()| self.restart_infinite(fen));
+                    let failed = result.is_err();
+                    let _ = reply.send(result);
+                    if failed {
+                        self.finish_session();
+                        return None;
+                    }
+                    accumulator = self.session_accumulator();
+                    searching = true;
+                }
+                Ok(EngineCommand::ApplyOptions { config, reply }) => {
+                    let result = self.reapply_options(&mut accumulator, searching, &config);
+                    let failed = result.is_err();
+                    let _ = reply.send(result);
+                    if failed {
+                        self.finish_session();
+                        return None;
+                    }
+                    accumulator = self.session_accumulator();
+                    searching = true;
+                }
+                Ok(command @ EngineCommand::Install { .. }) => {
+                    let _ = self.stop_infinite(&mut accumulator, searching);
+                    self.finish_session();
+                    return Some(command);
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => return None,
+            }
+
+            let Some(engine) = self.process.as_mut() else {
+                self.finish_session();
+                return None;
+            };
+            match engine.read_line(INFINITE_POLL_INTERVAL) {
+                Ok(Some(line)) => {
+                    if let Some(info) = parse_info_line(&line) {
+                        if accumulator.update(info_to_analysis_line(info)).is_some() {
+                            self.publish_lines(&accumulator);
+                        }
+                    } else if let Some((best_move, ponder)) = parse_bestmove_line(&line) {
+                        // Only happens when the position has no moves left to search.
+                        searching = false;
+                        self.session.send_modify(|session| {
+                            if let Some(session) = session {
+                                session.best_move = best_move;
+                                session.ponder = ponder;
+                            }
+                        });
+                    }
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    self.crashed(format!("Engine process exited during analysis: {err}"));
+                    self.finish_session();
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Sends `stop` and waits for the closing `bestmove`, keeping the final lines.
+    fn stop_infinite(
+        &mut self,
+        accumulator: &mut AnalysisAccumulator,
+        searching: bool,
+    ) -> Result<(), EngineManagerError> {
+        if !searching {
+            return Ok(());
+        }
+
+        let engine = self.engine()?;
+        if let Err(err) = engine.write_line("stop") {
+            return Err(self.search_failed(err));
+        }
+
+        let deadline = Instant::now() + STOP_TIMEOUT;
+        while Instant::now() < deadline {
+            let engine = self.engine()?;
+            match engine.read_line(INFINITE_POLL_INTERVAL) {
+                Ok(Some(line)) => {
+                    if let Some(info) = parse_info_line(&line) {
+                        accumulator.update(info_to_analysis_line(info));
+                    } else if let Some((best_move, ponder)) = parse_bestmove_line(&line) {
+                        let lines = accumulator.lines();
+                        self.session.send_modify(|session| {
+                            if let Some(session) = session {
+                                session.lines = lines;
+                                session.best_move = best_move;
+                                session.ponder = ponder;
+                            }
+                        });
+                        return Ok(());
+                    }
+                }
+                Ok(None) => {}
+                Err(err) => return Err(self.search_failed(err)),
+            }
+        }
+
+        Err(self.search_failed(StockfishError::Timeout("bestmove")))
+    }
+
+    /// UCI engines only accept `setoption` while idle, so pause the search around it.
+    fn reapply_options(
+        &mut self,
+        accumulator: &mut AnalysisAccumulator,
+        searching: bool,
+        config: &AppConfig,
+    ) -> Result<(), EngineManagerError> {
+        self.stop_infinite(accumulator, searching)?;
+        let fen = self.current_session_fen();
+        let applied = apply_options_to_process(self.engine()?, config);
+        applied.map_err(|err| self.search_failed(err))?;
+        self.restart_infinite(fen)?;
+        Ok(())
+    }
+
+    fn restart_infinite(&mut self, fen: String) -> Result<InfiniteSession, EngineManagerError> {
+        let engine = self.engine()?;
+        if let Err(err) = engine.go_infinite(&fen) {
+            return Err(self.search_failed(err));
+        }
+
+        self.session.send_modify(|session| {
+            if let Some(session) = session {
+                session.fen = fen;
+                session.started_at = Instant::now();
+                session.lines.clear();
+                session.best_move = None;
+                session.ponder = None;
+            }
+        });
+        Ok(self
+            .session
+            .borrow()
+            .clone()
+            .expect("infinite session is active"))
+    }
+
+    fn session_accumulator(&self) -> AnalysisAccumulator {
+        AnalysisAccumulator::for_position(&UciPosition::from_fen(self.current_session_fen()))
+    }
+
+    fn current_session_fen(&self) -> String {
+        self.session
+            .borrow()
+            .as_ref()
+            .map(|session| session.fen.clone())
+            .unwrap_or_default()
+    }
+
+    fn publish_lines(&self, accumulator: &AnalysisAccumulator) {
+        let lines = accumulator.lines();
+        self.session.send_modify(|session| {
+            if let Some(session) = session {
+                session.lines = lines;
+            }
+        });
+    }
+
+    /// Marks the session as stopped and returns the engine to `ready`.
+    fn finish_session(&mut self) -> InfiniteSession {
+        self.session.send_modify(|session| {
+            if let Some(session) = session {
+                session.stopped_at.get_or_insert_with(Instant::now);
+            }
+        });
+        if self.process.is_some() {
+            self.snapshot.send_modify(|s| {
+                s.status = EngineStatus::Ready;
+                s.current_job_id = None;
+            });
+        }
+        self.session
+            .borrow()
+            .clone()
+            .expect("infinite session is active")
+    }
+
+    fn search_failed(&mut self, err: StockfishError) -> EngineManagerError {
+        let exited = self
+            .process
+            .as_mut()
+            .is_none_or(|engine| engine.has_exited());
+        if exited || matches!(err, StockfishError::EngineExited) {
+            self.crashed(format!("Engine process exited during analysis: {err}"));
+            EngineManagerError::Stockfish(StockfishError::EngineExited)
+        } else {
+            self.snapshot.send_modify(|s| {
+                s.status = EngineStatus::Error;
+                s.current_job_id = None;
+                s.last_error = Some(err.to_string());
+            });
+            EngineManagerError::Stockfish(err)
+        }
+    }
+
+    fn crashed(&mut self, reason: String) {
+        tracing::warn!(%reason, "chess engine crashed");
+        self.process = None;
+        self.snapshot.send_modify(|s| {
+            s.has_process = false;
+            s.status = EngineStatus::Crashed;
+            s.current_job_id = None;
+            s.last_error = Some(reason.clone());
+            s.last_crash_reason = Some(reason);
+        });
+    }
+}
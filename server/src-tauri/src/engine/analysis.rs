@@ -1,22 +1,115 @@
 use std::collections::BTreeMap;
 
-use crate::api::types::AnalysisLine;
+use serde::{Deserialize, Serialize};
+use shakmaty::{Chess, Color, Position};
 
-#[derive(Debug, Clone)]
+use crate::{
+    api::types::AnalysisLine,
+    engine::{
+        notation,
+        uci::{ParsedInfo, UciPosition},
+        wdl,
+    },
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RawAnalysisResult {
     pub best_move: String,
     pub ponder: Option<String>,
     pub lines: Vec<AnalysisLine>,
+    pub totals: SearchTotals,
+    /// The search was stopped early because its request was cancelled.
+    pub cancelled: bool,
+}
+
+/// Whole-search counters, taken from the latest `info` line that reported them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchTotals {
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    pub hashfull: Option<u32>,
+    pub tbhits: Option<u64>,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct AnalysisAccumulator {
     lines: BTreeMap<u8, AnalysisLine>,
+    totals: SearchTotals,
+    white_to_move: bool,
+    /// Position the lines start from, used to write them in SAN.
+    position: Option<Chess>,
+}
+
+impl Default for AnalysisAccumulator {
+    fn default() -> Self {
+        Self::new(true)
+    }
 }
 
 impl AnalysisAccumulator {
-    pub fn update(&mut self, line: AnalysisLine) {
-        self.lines.insert(line.rank, line);
+    /// `white_to_move` orients the White point-of-view scores on each line.
+    pub fn new(white_to_move: bool) -> Self {
+        Self {
+            lines: BTreeMap::new(),
+            totals: SearchTotals::default(),
+            white_to_move,
+            position: None,
+        }
+    }
+
+    /// An accumulator that also adds SAN to each line. Falls back to UCI only
+    /// when the position cannot be replayed.
+    pub fn for_position(position: &UciPosition) -> Self {
+        match notation::replay(position) {
+            Some(board) => Self {
+                white_to_move: board.turn() == Color::White,
+                position: Some(board),
+                ..Self::new(true)
+            },
+            None => Self::new(position.white_to_move()),
+        }
+    }
+
+    /// Keeps `line` as the latest for its rank and returns it, unless it has
+    /// no pv or score, or it is a bound score and an exact one is already held.
+    pub fn update(&mut self, mut line: AnalysisLine) -> Option<&AnalysisLine> {
+        if line.pv.is_empty() || (line.score_cp.is_none() && line.mate.is_none()) {
+            return None;
+        }
+
+        if line.bound.is_some() && self.lines.contains_key(&line.rank) {
+            return None;
+        }
+
+        wdl::complete_line(&mut line, self.white_to_move);
+        if let Some(position) = &self.position {
+            notation::annotate_line(position, &mut line);
+        }
+        let rank = line.rank;
+        self.lines.insert(rank, line);
+        self.lines.get(&rank)
+    }
+
+    pub fn record_totals(&mut self, info: &ParsedInfo) {
+        let totals = &mut self.totals;
+        totals.nodes = info.nodes.or(totals.nodes);
+        totals.nps = info.nps.or(totals.nps);
+        totals.hashfull = info.hashfull.or(totals.hashfull);
+        totals.tbhits = info.tbhits.or(totals.tbhits);
+    }
+
+    pub fn lines(&self) -> Vec<AnalysisLine> {
+        self.lines.values().cloned().collect()
+    }
+
+    pub fn into_result(self, best_move: String, ponder: Option<String>) -> RawAnalysisResult {
+        RawAnalysisResult {
+            best_move,
+            ponder,
+            lines: self.lines.into_values().collect(),
+            totals: self.totals,
+            cancelled: false,
+        }
     }
 
     pub fn into_lines(self) -> Vec<AnalysisLine> {
@@ -0,0 +1,148 @@
+use serde::Serialize;
+
+use crate::{
+    api::types::{AnalyzeRequest, AnalyzeResponse, BatchAnalyzeRequest, JobError},
+    engine::manager::EngineManagerError,
+};
+
+/// Most positions one batch may hold.
+pub const MAX_BATCH_POSITIONS: usize = 10_000;
+
+/// One position of a batch, answered in the order it was sent.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchItem {
+    pub index: usize,
+    /// The item's `request_id`, or the `id` operation of its EPD line.
+    pub id: Option<String>,
+    pub result: Option<AnalyzeResponse>,
+    /// Set instead of `result` when this position failed.
+    pub error: Option<JobError>,
+}
+
+impl BatchItem {
+    pub fn new(
+        index: usize,
+        id: Option<String>,
+        result: Result<AnalyzeResponse, EngineManagerError>,
+    ) -> Self {
+        match result {
+            Ok(response) => BatchItem {
+                index,
+                id,
+                result: Some(response),
+                error: None,
+            },
+            Err(err) => {
+                let (_, code, message) = err.to_api_parts();
+                BatchItem {
+                    index,
+                    id,
+                    result: None,
+                    error: Some(JobError { code, message }),
+                }
+            }
+        }
+    }
+}
+
+/// A position read from one line of an EPD file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpdLine {
+    /// The four EPD fields, completed with the `hmvc` and `fmvn`
+    /// operations or `0 1` when they are missing.
+    pub fen: String,
+    pub id: Option<String>,
+}
+
+/// Reads every non-empty line of `text`. Lines are not validated here: a
+/// malformed one becomes a FEN that fails on its own when analysed.
+pub fn parse_epd(text: &str) -> Vec<EpdLine> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(parse_epd_line)
+        .collect()
+}
+
+fn parse_epd_line(line: &str) -> EpdLine {
+    let mut rest = line;
+    let mut fields = Vec::with_capacity(4);
+    while fields.len() < 4 {
+        let trimmed = rest.trim_start();
+        let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+        if end == 0 {
+            break;
+        }
+        fields.push(&trimmed[..end]);
+        rest = &trimmed[end..];
+    }
+
+    let (mut id, mut halfmoves, mut fullmoves) = (None, "0", "1");
+    for operation in rest.split(';') {
+        let operation = operation.trim();
+        let (opcode, operand) = operation
+            .split_once(char::is_whitespace)
+            .unwrap_or((operation, ""));
+        let operand = operand.trim();
+        match opcode {
+            "id" => id = Some(operand.trim_matches('"').to_string()),
+            "hmvc" if !operand.is_empty() => halfmoves = operand,
+            "fmvn" if !operand.is_empty() => fullmoves = operand,
+            _ => {}
+        }
+    }
+
+    EpdLine {
+        fen: format!("{} {halfmoves} {fullmoves}", fields.join(" ")),
+        id,
+    }
+}
+
+/// Turns a batch into one request per position, each filled in with the
+/// batch's shared settings where it has none of its own.
+pub fn requests(
+    batch: BatchAnalyzeRequest,
+) -> Result<Vec<(Option<String>, AnalyzeRequest)>, EngineManagerError> {
+    let mut requests: Vec<_> = match (batch.positions.is_empty(), batch.epd) {
+        (false, Some(_)) => {
+            return Err(EngineManagerError::InvalidRequest(
+                "send either positions or epd, not both".to_string(),
+            ))
+        }
+        (false, None) => batch.positions,
+        (true, Some(epd)) => parse_epd(&epd)
+            .into_iter()
+            .map(|line| AnalyzeRequest {
+                fen: Some(line.fen),
+                request_id: line.id,
+                ..Default::default()
+            })
+            .collect(),
+        (true, None) => Vec::new(),
+    };
+    if requests.is_empty() {
+        return Err(EngineManagerError::InvalidRequest(
+            "a batch needs at least one position".to_string(),
+        ));
+    }
+    if requests.len() > MAX_BATCH_POSITIONS {
+        return Err(EngineManagerError::InvalidRequest(format!(
+            "a batch holds at most {MAX_BATCH_POSITIONS} positions"
+        )));
+    }
+
+    for request in &mut requests {
+        request.depth = request.depth.or(batch.depth);
+        request.max_think_time_ms = request.max_think_time_ms.or(batch.max_think_time_ms);
+        request.disregard_think_time = request.disregard_think_time.or(batch.disregard_think_time);
+        request.use_book = request.use_book.or(batch.use_book);
+        request.use_tablebase = request.use_tablebase.or(batch.use_tablebase);
+        request.include_threat = request.include_threat.or(batch.include_threat);
+        // Keeping the hash between positions is most of what a batch saves.
+        request.new_game = request.new_game.or(Some(false));
+    }
+    Ok(requests
+        .into_iter()
+        .map(|request| (request.request_id.clone(), request))
+        .collect())
+}
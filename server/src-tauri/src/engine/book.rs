@@ -0,0 +1,226 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::Serialize;
+use shakmaty::{
+    san::SanPlus, zobrist::Zobrist64, CastlingMode, Chess, Color, EnPassantMode, File, Move,
+    Position, Rank, Role, Square,
+};
+
+use crate::engine::manager::EngineManagerError;
+
+/// Size of one Polyglot entry: key, move, weight and learn data, big-endian.
+const ENTRY_LEN: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BookEntry {
+    key: u64,
+    raw_move: u16,
+    weight: u16,
+}
+
+/// A Polyglot opening book, held in memory and sorted by key.
+#[derive(Debug, Default)]
+pub struct PolyglotBook {
+    entries: Vec<BookEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BookMove {
+    pub uci: String,
+    pub san: String,
+    pub weight: u16,
+    /// Share of the total weight of the moves found, from 0 to 1.
+    pub probability: f64,
+}
+
+/// The moves one book has for a position, highest weight first.
+#[derive(Debug, Clone)]
+pub struct BookHit {
+    pub path: String,
+    pub moves: Vec<BookMove>,
+}
+
+impl PolyglotBook {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if !bytes.len().is_multiple_of(ENTRY_LEN) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a Polyglot book: its size is not a multiple of 16 bytes",
+            ));
+        }
+
+        let mut entries: Vec<BookEntry> = bytes
+            .chunks_exact(ENTRY_LEN)
+            .map(|chunk| BookEntry {
+                key: u64::from_be_bytes(chunk[0..8].try_into().expect("8-byte key")),
+                raw_move: u16::from_be_bytes([chunk[8], chunk[9]]),
+                weight: u16::from_be_bytes([chunk[10], chunk[11]]),
+            })
+            .collect();
+        // Books are sorted by key by definition, but a hand-made one may not be.
+        if !entries.is_sorted_by_key(|entry| entry.key) {
+            entries.sort_by_key(|entry| entry.key);
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Legal book moves for `position` weighing at least `min_weight`,
+    /// highest weight first.
+    pub fn probe(&self, position: &Chess, min_weight: u16) -> Vec<BookMove> {
+        let key = polyglot_key(position);
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let mut found: Vec<(Move, u16)> = self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .filter(|entry| entry.weight >= min_weight)
+            .filter_map(|entry| Some((decode_move(position, entry.raw_move)?, entry.weight)))
+            .collect();
+        found.sort_by(|a, b| b.1.cmp(&a.1));
+
+        let total: u32 = found.iter().map(|(_, weight)| u32::from(*weight)).sum();
+        found
+            .into_iter()
+            .map(|(legal, weight)| BookMove {
+                uci: legal.to_uci(CastlingMode::Standard).to_string(),
+                san: SanPlus::from_move(position.clone(), legal).to_string(),
+                weight,
+                probability: if total > 0 {
+                    f64::from(weight) / f64::from(total)
+                } else {
+                    0.0
+                },
+            })
+            .collect()
+    }
+}
+
+/// The Polyglot key of `position`. shakmaty's Zobrist hash uses the Polyglot
+/// random numbers; Polyglot counts an en passant square whenever a pawn
+/// stands next to the double-pushed one, legal capture or not.
+pub fn polyglot_key(position: &Chess) -> u64 {
+    position
+        .zobrist_hash::<Zobrist64>(EnPassantMode::PseudoLegal)
+        .0
+}
+
+/// Half-moves played since the start of the game, from the move counters.
+pub fn game_ply(position: &Chess) -> u32 {
+    let ply = (position.fullmoves().get() - 1) * 2;
+    match position.turn() {
+        Color::White => ply,
+        Color::Black => ply + 1,
+    }
+}
+
+/// Books loaded from the configured paths, reloaded when a file changes.
+#[derive(Debug, Default)]
+pub struct BookShelf {
+    books: Vec<ShelvedBook>,
+}
+
+#[derive(Debug)]
+struct ShelvedBook {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    len: u64,
+    book: PolyglotBook,
+}
+
+impl BookShelf {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Probes `paths` in order; the first book with moves for `position` answers.
+    pub fn probe(
+        &mut self,
+        paths: &[String],
+        position: &Chess,
+        min_weight: u16,
+    ) -> Result<Option<BookHit>, EngineManagerError> {
+        self.refresh(paths)?;
+        Ok(self.books.iter().find_map(|shelved| {
+            let moves = shelved.book.probe(position, min_weight);
+            (!moves.is_empty()).then(|| BookHit {
+                path: shelved.path.display().to_string(),
+                moves,
+            })
+        }))
+    }
+
+    /// Loads books that are new or changed on disk and drops the ones no
+    /// longer configured.
+    fn refresh(&mut self, paths: &[String]) -> Result<(), EngineManagerError> {
+        let mut books = Vec::with_capacity(paths.len());
+        for path in paths.iter().map(PathBuf::from) {
+            let error = |source| EngineManagerError::OpeningBook {
+                path: path.display().to_string(),
+                source,
+            };
+            let metadata = fs::metadata(&path).map_err(error)?;
+            let modified = metadata.modified().ok();
+            let kept = self.books.iter().position(|shelved| {
+                shelved.path == path
+                    && shelved.modified == modified
+                    && shelved.len == metadata.len()
+            });
+            let shelved = match kept {
+                Some(index) => self.books.swap_remove(index),
+                None => {
+                    let book = PolyglotBook::open(&path).map_err(error)?;
+                    tracing::info!(path = %path.display(), entries = book.len(), "loaded opening book");
+                    ShelvedBook {
+                        path,
+                        modified,
+                        len: metadata.len(),
+                        book,
+                    }
+                }
+            };
+            books.push(shelved);
+        }
+        self.books = books;
+        Ok(())
+    }
+}
+
+/// Turns a Polyglot move into the legal move it stands for. Castling is
+/// stored as the king taking its own rook, which is how shakmaty reports it.
+fn decode_move(position: &Chess, raw: u16) -> Option<Move> {
+    let square = |bits: u16| {
+        Square::from_coords(
+            File::new(u32::from(bits & 7)),
+            Rank::new(u32::from((bits >> 3) & 7)),
+        )
+    };
+    let to = square(raw);
+    let from = square(raw >> 6);
+    let promotion = match (raw >> 12) & 7 {
+        0 => None,
+        1 => Some(Role::Knight),
+        2 => Some(Role::Bishop),
+        3 => Some(Role::Rook),
+        4 => Some(Role::Queen),
+        _ => return None,
+    };
+
+    position.legal_moves().into_iter().find(|legal| {
+        legal.from() == Some(from) && legal.to() == to && legal.promotion() == promotion
+    })
+}
@@ -0,0 +1,130 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::analysis::RawAnalysisResult;
+
+/// What makes two analyses interchangeable, apart from their depth.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CacheKey {
+    /// Zobrist hash of the analysed position (move counters excluded).
+    pub zobrist: u64,
+    /// [`history_hash`] of the positions the engine can see repeated.
+    /// Records written before it existed read as 0, like a bare FEN.
+    #[serde(default)]
+    pub history: u64,
+    /// `None` for searches that ignore the think time.
+    pub movetime_ms: Option<u64>,
+    pub multipv: u8,
+    /// Engine path, name and UCI options, so results from another engine or
+    /// another configuration never match.
+    pub engine: String,
+}
+
+/// Combines the Zobrist hashes of the positions played since the last
+/// capture or pawn move, which the engine counts for repetitions. The order
+/// does not matter but repeats do, and no earlier positions hash to 0.
+pub fn history_hash(earlier: &[u64]) -> u64 {
+    earlier
+        .iter()
+        .fold(0, |hash: u64, zobrist| hash.wrapping_add(*zobrist))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedAnalysis {
+    /// Depth the search was asked for.
+    pub depth: u32,
+    pub raw: RawAnalysisResult,
+    pub engine_name: Option<String>,
+}
+
+/// Least-recently-used cache of finished analyses.
+#[derive(Debug, Default)]
+pub struct AnalysisCache {
+    entries: HashMap<CacheKey, Entry>,
+    /// Every key by its `last_used` stamp, oldest first, so evicting does
+    /// not scan the entries.
+    recency: BTreeMap<u64, CacheKey>,
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    analysis: CachedAnalysis,
+    last_used: u64,
+}
+
+impl AnalysisCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a cached analysis searched at least `depth` deep.
+    pub fn get(&mut self, key: &CacheKey, depth: u32) -> Option<CachedAnalysis> {
+        let now = self.tick();
+        let entry = self.entries.get_mut(key)?;
+        if entry.analysis.depth < depth {
+            return None;
+        }
+        let used = std::mem::replace(&mut entry.last_used, now);
+        let analysis = entry.analysis.clone();
+        self.recency.remove(&used);
+        self.recency.insert(now, key.clone());
+        Some(analysis)
+    }
+
+    /// Stores an analysis, evicting the least recently used ones beyond
+    /// `capacity`. A deeper analysis already cached for the key is kept.
+    pub fn insert(&mut self, key: CacheKey, analysis: CachedAnalysis, capacity: usize) {
+        if capacity == 0 {
+            self.clear();
+            return;
+        }
+
+        let now = self.tick();
+        self.recency.insert(now, key.clone());
+        match self.entries.get_mut(&key) {
+            Some(entry) if entry.analysis.depth > analysis.depth => {
+                let used = std::mem::replace(&mut entry.last_used, now);
+                self.recency.remove(&used);
+            }
+            _ => {
+                let replaced = self.entries.insert(
+                    key,
+                    Entry {
+                        analysis,
+                        last_used: now,
+                    },
+                );
+                if let Some(replaced) = replaced {
+                    self.recency.remove(&replaced.last_used);
+                }
+            }
+        }
+
+        while self.entries.len() > capacity {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
@@ -298,6 +298,7 @@ mod tests {
             score_cp: Some(cp),
             mate: None,
             pv: vec![format!("move{rank}")],
+            ..Default::default()
         }
     }
 
@@ -309,6 +310,7 @@ mod tests {
             score_cp: None,
             mate: Some(mate),
             pv: vec![format!("move{rank}")],
+            ..Default::default()
         }
     }
 
@@ -0,0 +1,304 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::engine::cache::{CacheKey, CachedAnalysis};
+
+const LOG_FILE: &str = "analyses.jsonl";
+/// A compaction shrinks the log to this share of the cap, so the next few
+/// appends do not trigger another rewrite straight away.
+const COMPACT_TARGET_PERCENT: u64 = 75;
+
+/// One line of the log.
+#[derive(Serialize, Deserialize)]
+struct Record {
+    key: CacheKey,
+    saved_at: DateTime<Utc>,
+    analysis: CachedAnalysis,
+}
+
+/// Where the latest record for a key sits in the log.
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    offset: u64,
+    len: u64,
+    depth: u32,
+    last_used: u64,
+}
+
+/// Finished analyses kept across restarts in an append-only JSON-lines log.
+///
+/// The index lives in memory and is rebuilt by scanning the log on open.
+/// Lines that do not parse are skipped and counted, and a torn last line
+/// from an interrupted write is cut off. Once the log outgrows its cap it is
+/// rewritten with the most recently used entries only.
+#[derive(Debug)]
+pub struct DiskCache {
+    dir: PathBuf,
+    index: HashMap<CacheKey, Slot>,
+    size_bytes: u64,
+    skipped_records: usize,
+    clock: u64,
+}
+
+impl DiskCache {
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let mut cache = Self {
+            dir: dir.to_path_buf(),
+            index: HashMap::new(),
+            size_bytes: 0,
+            skipped_records: 0,
+            clock: 0,
+        };
+        cache.rebuild_index()?;
+        Ok(cache)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Size of the log, including records that were replaced or skipped.
+    pub fn size_bytes(&self) -> u64 {
+        self.size_bytes
+    }
+
+    /// Records dropped because they could not be read back.
+    pub fn skipped_records(&self) -> usize {
+        self.skipped_records
+    }
+
+    /// Returns a stored analysis searched at least `depth` deep.
+    pub fn get(&mut self, key: &CacheKey, depth: u32) -> io::Result<Option<CachedAnalysis>> {
+        let Some(slot) = self.index.get(key).copied() else {
+            return Ok(None);
+        };
+        if slot.depth < depth {
+            return Ok(None);
+        }
+
+        let record = match self.read_slot(&mut File::open(self.log_path())?, slot) {
+            Ok(bytes) => serde_json::from_slice::<Record>(&bytes).ok(),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => None,
+            Err(err) => return Err(err),
+        };
+        match record {
+            Some(record) if record.key == *key => {
+                let now = self.tick();
+                if let Some(slot) = self.index.get_mut(key) {
+                    slot.last_used = now;
+                }
+                Ok(Some(record.analysis))
+            }
+            _ => {
+                self.index.remove(key);
+                self.skipped_records += 1;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Appends an analysis unless one at least as deep is stored for the key,
+    /// then compacts the log if it grew past `max_bytes`.
+    pub fn insert(
+        &mut self,
+        key: CacheKey,
+        analysis: CachedAnalysis,
+        max_bytes: u64,
+    ) -> io::Result<()> {
+        let now = self.tick();
+        if let Some(slot) = self.index.get_mut(&key) {
+            if slot.depth >= analysis.depth {
+                slot.last_used = now;
+                return Ok(());
+            }
+        }
+
+        let depth = analysis.depth;
+        let mut line = serde_json::to_vec(&Record {
+            key: key.clone(),
+            saved_at: Utc::now(),
+            analysis,
+        })?;
+        line.push(b'\n');
+        let len = line.len() as u64;
+        if len > max_bytes {
+            return Ok(());
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.log_path())?;
+        file.write_all(&line)?;
+        self.index.insert(
+            key,
+            Slot {
+                offset: self.size_bytes,
+                len,
+                depth,
+                last_used: now,
+            },
+        );
+        self.size_bytes += len;
+
+        if self.size_bytes > max_bytes {
+            self.compact(max_bytes * COMPACT_TARGET_PERCENT / 100)?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the log and forgets every entry.
+    pub fn clear(&mut self) -> io::Result<()> {
+        match fs::remove_file(self.log_path()) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+        self.index.clear();
+        self.size_bytes = 0;
+        self.skipped_records = 0;
+        Ok(())
+    }
+
+    fn log_path(&self) -> PathBuf {
+        self.dir.join(LOG_FILE)
+    }
+
+    /// Scans the log, keeping the deepest record per key. Later records count
+    /// as more recently used, since they were written later.
+    fn rebuild_index(&mut self) -> io::Result<()> {
+        let file = match File::open(self.log_path()) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err),
+        };
+
+        let mut reader = BufReader::new(file);
+        let mut offset = 0;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let len = reader.read_until(b'\n', &mut line)? as u64;
+            if len == 0 {
+                break;
+            }
+            if line.last() != Some(&b'\n') {
+                tracing::warn!(
+                    offset,
+                    "dropping a torn record at the end of the analysis cache"
+                );
+                self.skipped_records += 1;
+                OpenOptions::new()
+                    .write(true)
+                    .open(self.log_path())?
+                    .set_len(offset)?;
+                break;
+            }
+
+            match serde_json::from_slice::<Record>(&line) {
+                Ok(record) => {
+                    let depth = record.analysis.depth;
+                    let now = self.tick();
+                    let keep_existing = self
+                        .index
+                        .get(&record.key)
+                        .is_some_and(|slot| slot.depth > depth);
+                    if !keep_existing {
+                        self.index.insert(
+                            record.key,
+                            Slot {
+                                offset,
+                                len,
+                                depth,
+                                last_used: now,
+                            },
+                        );
+                    }
+                }
+                Err(_) => self.skipped_records += 1,
+            }
+            offset += len;
+        }
+
+        self.size_bytes = offset;
+        if self.skipped_records > 0 {
+            tracing::warn!(
+                skipped = self.skipped_records,
+                "skipped unreadable records in the analysis cache"
+            );
+        }
+        Ok(())
+    }
+
+    /// Rewrites the log with the most recently used entries that fit in
+    /// `budget` bytes, least recently used first so a reload keeps the order.
+    fn compact(&mut self, budget: u64) -> io::Result<()> {
+        let mut slots: Vec<(CacheKey, Slot)> = self
+            .index
+            .iter()
+            .map(|(key, slot)| (key.clone(), *slot))
+            .collect();
+        slots.sort_by(|a, b| b.1.last_used.cmp(&a.1.last_used));
+
+        let mut kept_bytes = 0;
+        let kept = slots
+            .into_iter()
+            .take_while(|(_, slot)| {
+                kept_bytes += slot.len;
+                kept_bytes <= budget
+            })
+            .collect::<Vec<_>>();
+
+        let mut source = File::open(self.log_path())?;
+        let temp_path = self.dir.join(format!("{LOG_FILE}.tmp"));
+        let mut writer = BufWriter::new(File::create(&temp_path)?);
+        let mut index = HashMap::with_capacity(kept.len());
+        let mut offset = 0;
+        for (key, slot) in kept.into_iter().rev() {
+            let bytes = self.read_slot(&mut source, slot)?;
+            writer.write_all(&bytes)?;
+            index.insert(key, Slot { offset, ..slot });
+            offset += slot.len;
+        }
+        writer.into_inner()?.sync_all()?;
+        fs::rename(&temp_path, self.log_path())?;
+
+        tracing::info!(
+            evicted = self.index.len() - index.len(),
+            size_bytes = offset,
+            "compacted the analysis cache"
+        );
+        self.index = index;
+        self.size_bytes = offset;
+        self.skipped_records = 0;
+        Ok(())
+    }
+
+    fn read_slot(&self, file: &mut File, slot: Slot) -> io::Result<Vec<u8>> {
+        let mut bytes = vec![0; slot.len as usize];
+        file.seek(SeekFrom::Start(slot.offset))?;
+        file.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
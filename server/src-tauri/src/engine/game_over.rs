@@ -0,0 +1,81 @@
+use serde::Serialize;
+use shakmaty::{Chess, Color, Position};
+
+/// Half-moves without a capture or pawn move after which a draw can be claimed.
+const FIFTY_MOVE_HALFMOVES: u32 = 100;
+/// Half-moves without a capture or pawn move after which the game is drawn.
+const SEVENTY_FIVE_MOVE_HALFMOVES: u32 = 150;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameOutcome {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameOverReason {
+    Checkmate,
+    Stalemate,
+    InsufficientMaterial,
+    FiftyMoveRule,
+    SeventyFiveMoveRule,
+}
+
+/// Why there is nothing left to analyse in a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct GameOver {
+    pub outcome: GameOutcome,
+    pub reason: GameOverReason,
+}
+
+impl GameOver {
+    pub fn describe(&self) -> &'static str {
+        match self.reason {
+            GameOverReason::Checkmate => "checkmate",
+            GameOverReason::Stalemate => "stalemate",
+            GameOverReason::InsufficientMaterial => "insufficient material",
+            GameOverReason::FiftyMoveRule => "the fifty-move rule",
+            GameOverReason::SeventyFiveMoveRule => "the seventy-five-move rule",
+        }
+    }
+}
+
+/// Returns the result of a finished game, or `None` while there are moves to
+/// search.
+///
+/// Checkmate wins over the move-count rules, since a mate delivered on the
+/// 100th or 150th half-move still ends the game.
+pub fn detect(position: &Chess) -> Option<GameOver> {
+    let draw = |reason| {
+        Some(GameOver {
+            outcome: GameOutcome::Draw,
+            reason,
+        })
+    };
+
+    if position.is_checkmate() {
+        return Some(GameOver {
+            outcome: match position.turn() {
+                Color::White => GameOutcome::BlackWins,
+                Color::Black => GameOutcome::WhiteWins,
+            },
+            reason: GameOverReason::Checkmate,
+        });
+    }
+    if position.is_stalemate() {
+        return draw(GameOverReason::Stalemate);
+    }
+    if position.is_insufficient_material() {
+        return draw(GameOverReason::InsufficientMaterial);
+    }
+    if position.halfmoves() >= SEVENTY_FIVE_MOVE_HALFMOVES {
+        return draw(GameOverReason::SeventyFiveMoveRule);
+    }
+    if position.halfmoves() >= FIFTY_MOVE_HALFMOVES {
+        return draw(GameOverReason::FiftyMoveRule);
+    }
+    None
+}
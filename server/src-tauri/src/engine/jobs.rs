@@ -0,0 +1,346 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::{
+    api::types::{AnalysisLine, JobError, JobProgress, JobResponse, JobResult},
+    engine::manager::EngineManagerError,
+};
+
+/// Analyses that are queued or running, each with its own cancel token.
+#[derive(Default)]
+pub struct JobRegistry {
+    running: Mutex<Vec<RunningJob>>,
+}
+
+struct RunningJob {
+    request_id: String,
+    cancel: Arc<AtomicBool>,
+    /// Set once the job has an engine worker.
+    started: Arc<AtomicBool>,
+}
+
+impl JobRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `request_id` until the returned guard is dropped.
+    pub fn register(self: &Arc<Self>, request_id: &str) -> Result<JobGuard, EngineManagerError> {
+        let mut running = self.lock();
+        if running.iter().any(|job| job.request_id == request_id) {
+            return Err(EngineManagerError::InvalidRequest(format!(
+                "an analysis with request_id {request_id} is already running"
+            )));
+        }
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let started = Arc::new(AtomicBool::new(false));
+        running.push(RunningJob {
+            request_id: request_id.to_string(),
+            cancel: cancel.clone(),
+            started: started.clone(),
+        });
+        Ok(JobGuard {
+            registry: self.clone(),
+            request_id: request_id.to_string(),
+            cancel,
+            started,
+        })
+    }
+
+    /// Whether a registered job has reached an engine worker yet.
+    pub fn is_started(&self, request_id: &str) -> bool {
+        self.lock()
+            .iter()
+            .any(|job| job.request_id == request_id && job.started.load(Ordering::SeqCst))
+    }
+
+    /// Cancels one analysis. Returns `false` when it is not running.
+    pub fn cancel(&self, request_id: &str) -> bool {
+        match self.lock().iter().find(|job| job.request_id == request_id) {
+            Some(job) => {
+                job.cancel.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cancels every analysis and returns their request IDs, oldest first.
+    pub fn cancel_all(&self) -> Vec<String> {
+        self.lock()
+            .iter()
+            .filter(|job| !job.cancel.swap(true, Ordering::SeqCst))
+            .map(|job| job.request_id.clone())
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<RunningJob>> {
+        self.running.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Keeps a job registered; removes it again when dropped.
+pub struct JobGuard {
+    registry: Arc<JobRegistry>,
+    request_id: String,
+    cancel: Arc<AtomicBool>,
+    started: Arc<AtomicBool>,
+}
+
+impl JobGuard {
+    pub fn mark_started(&self) {
+        self.started.store(true, Ordering::SeqCst);
+    }
+
+    pub fn cancel_token(&self) -> Arc<AtomicBool> {
+        self.cancel.clone()
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for JobGuard {
+    fn drop(&mut self) {
+        self.registry
+            .lock()
+            .retain(|job| job.request_id != self.request_id);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    Analysis,
+    Review,
+}
+
+/// Analyses submitted through `/jobs` and game reviews, kept until their
+/// results expire.
+#[derive(Default)]
+pub struct JobStore {
+    records: Mutex<HashMap<String, JobRecord>>,
+}
+
+struct JobRecord {
+    kind: JobKind,
+    submitted_at: DateTime<Utc>,
+    submitted: Instant,
+    target_depth: u32,
+    /// How long the job is kept once finished.
+    retention: Duration,
+    lines: BTreeMap<u8, AnalysisLine>,
+    /// Positions analysed so far and in total, for reviews.
+    plies: Option<(usize, usize)>,
+    finished: Option<FinishedJob>,
+}
+
+struct FinishedJob {
+    at: DateTime<Utc>,
+    instant: Instant,
+    elapsed_ms: u128,
+    outcome: JobOutcome,
+}
+
+impl JobRecord {
+    fn expired(&self) -> bool {
+        self.finished
+            .as_ref()
+            .is_some_and(|finished| finished.instant.elapsed() >= self.retention)
+    }
+}
+
+enum JobOutcome {
+    Completed(JobResult),
+    Failed(JobError),
+    Cancelled(JobError),
+}
+
+impl JobStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a job that is kept for `retention` once finished, dropping
+    /// expired jobs first.
+    ///
+    /// A finished job with the same ID is replaced; an unfinished one is an error.
+    pub fn insert(
+        &self,
+        job_id: &str,
+        kind: JobKind,
+        target_depth: u32,
+        retention: Duration,
+    ) -> Result<(), EngineManagerError> {
+        let mut records = self.lock();
+        records.retain(|_, record| !record.expired());
+
+        if records
+            .get(job_id)
+            .is_some_and(|record| record.finished.is_none())
+        {
+            return Err(EngineManagerError::InvalidRequest(format!(
+                "a job with id {job_id} is already running"
+            )));
+        }
+
+        records.insert(
+            job_id.to_string(),
+            JobRecord {
+                kind,
+                submitted_at: Utc::now(),
+                submitted: Instant::now(),
+                target_depth,
+                retention,
+                lines: BTreeMap::new(),
+                plies: None,
+                finished: None,
+            },
+        );
+        Ok(())
+    }
+
+    /// Keeps the latest line per MultiPV rank while the job runs.
+    pub fn record_line(&self, job_id: &str, line: AnalysisLine) {
+        if let Some(record) = self.lock().get_mut(job_id) {
+            record.lines.insert(line.rank, line);
+        }
+    }
+
+    /// Notes how many of a review's positions have been analysed.
+    pub fn record_plies(&self, job_id: &str, done: usize, total: usize) {
+        if let Some(record) = self.lock().get_mut(job_id) {
+            record.plies = Some((done, total));
+        }
+    }
+
+    pub fn finish(&self, job_id: &str, result: Result<JobResult, EngineManagerError>) {
+        let mut records = self.lock();
+        let Some(record) = records.get_mut(job_id) else {
+            return;
+        };
+
+        let outcome = match result {
+            Ok(result) => {
+                if let JobResult::Analysis(response) = &result {
+                    record.lines = response
+                        .lines
+                        .iter()
+                        .map(|line| (line.rank, line.clone()))
+                        .collect();
+                }
+                JobOutcome::Completed(result)
+            }
+            Err(err) => {
+                let cancelled = matches!(err, EngineManagerError::AnalysisCancelled { .. });
+                if let EngineManagerError::AnalysisCancelled { lines, .. } = &err {
+                    record
+                        .lines
+                        .extend(lines.iter().map(|line| (line.rank, line.clone())));
+                }
+                let (_, code, message) = err.to_api_parts();
+                let error = JobError { code, message };
+                if cancelled {
+                    JobOutcome::Cancelled(error)
+                } else {
+                    JobOutcome::Failed(error)
+                }
+            }
+        };
+
+        record.finished = Some(FinishedJob {
+            at: Utc::now(),
+            instant: Instant::now(),
+            elapsed_ms: record.submitted.elapsed().as_millis(),
+            outcome,
+        });
+    }
+
+    /// The job as the API reports it; `started` tells a queued job from a running one.
+    /// A job past its retention is dropped here and reported as unknown.
+    pub fn response(&self, job_id: &str, started: bool) -> Option<JobResponse> {
+        let mut records = self.lock();
+        if records.get(job_id)?.expired() {
+            records.remove(job_id);
+            return None;
+        }
+        let record = records.get(job_id)?;
+
+        let (state, result, error) = match record.finished.as_ref().map(|f| &f.outcome) {
+            None if started => (JobState::Running, None, None),
+            None => (JobState::Queued, None, None),
+            Some(JobOutcome::Completed(result)) => {
+                (JobState::Completed, Some(result.clone()), None)
+            }
+            Some(JobOutcome::Failed(error)) => (JobState::Failed, None, Some(error.clone())),
+            Some(JobOutcome::Cancelled(error)) => (JobState::Cancelled, None, Some(error.clone())),
+        };
+        let lines: Vec<AnalysisLine> = record.lines.values().cloned().collect();
+
+        Some(JobResponse {
+            ok: true,
+            job_id: job_id.to_string(),
+            kind: record.kind,
+            state,
+            submitted_at: record.submitted_at,
+            finished_at: record.finished.as_ref().map(|finished| finished.at),
+            progress: JobProgress {
+                depth: lines.iter().filter_map(|line| line.depth).max(),
+                target_depth: record.target_depth,
+                plies_analysed: record.plies.map(|(done, _)| done),
+                plies_total: record.plies.map(|(_, total)| total),
+                elapsed_ms: record
+                    .finished
+                    .as_ref()
+                    .map(|finished| finished.elapsed_ms)
+                    .unwrap_or_else(|| record.submitted.elapsed().as_millis()),
+            },
+            lines,
+            result,
+            error,
+        })
+    }
+
+    /// Forgets a finished job. Returns `false` when it is unknown, expired or
+    /// still running.
+    pub fn remove_finished(&self, job_id: &str) -> bool {
+        let mut records = self.lock();
+        match records.get(job_id) {
+            Some(record) if record.finished.is_some() => {
+                let expired = record.expired();
+                records.remove(job_id);
+                !expired
+            }
+            _ => false,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, JobRecord>> {
+        self.records.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
@@ -1,33 +1,81 @@
 use std::{
+    collections::{BTreeMap, VecDeque},
     path::PathBuf,
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc, Mutex,
+        Arc, Mutex, OnceLock,
     },
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 use axum::http::StatusCode;
-use shakmaty::{fen::Fen, CastlingMode, Chess, Position};
+use shakmaty::{
+    fen::{Epd, Fen},
+    uci::UciMove,
+    zobrist::Zobrist64,
+    CastlingMode, Chess, EnPassantMode, Position,
+};
 use thiserror::Error;
+use tokio::sync::mpsc::UnboundedSender;
 use uuid::Uuid;
 
 use crate::{
-    api::types::{AnalyzeRequest, AnalyzeResponse, EngineStatusResponse, EngineSummary},
+    api::types::{
+        AnalysisCacheResponse, AnalysisLine, AnalysisSource, AnalyzeRequest, AnalyzeResponse,
+        BatchAnalyzeRequest, BatchAnalyzeResponse, DiskCacheResponse, EngineStatusResponse,
+        EngineSummary, EvaluateMoveRequest, EvaluateMoveResponse, InfiniteAnalysisRequest,
+        InfiniteAnalysisResponse, JobResponse, JobResult, PositionRequest, PositionResponse,
+        ReviewRequest, TablebaseFoldersResponse, TablebaseResponse, WorkerStatusResponse,
+    },
     config::{model::AppConfig, store::ConfigStore},
     engine::{
+        actor::{
+            EngineActor, EngineCommand, EngineSnapshot, InfiniteSession, SearchOutcome,
+            SearchParams,
+        },
+        analysis::RawAnalysisResult,
+        batch::{self, BatchItem},
+        book::{self, BookHit, BookShelf},
+        cache::{self, AnalysisCache, CacheKey, CachedAnalysis},
         difficulty::{self, DifficultyInput},
+        disk_cache::DiskCache,
+        game_over::{self, GameOver},
         installer,
-        stockfish::{StockfishError, StockfishProcess},
+        jobs::{JobGuard, JobKind, JobRegistry, JobStore},
+        move_eval, notation,
+        openings::{Opening, OpeningTable, OPENINGS_DIR},
+        pool::{EnginePool, Worker},
+        position_report::{self, PositionReport},
+        review::{self, GameReview, ReviewGame},
+        stockfish::{EngineLaunch, StockfishError, StockfishProcess},
+        tablebase::{self, TablebaseProbe, Tablebases},
+        threat::{self, Threat},
+        uci::{UciOption, UciPosition},
+        wdl,
     },
 };
 
+/// Delay before the first automatic restart; doubled for each further restart in the window.
+const RESTART_BACKOFF_BASE: Duration = Duration::from_millis(250);
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(8);
+/// The supervisor gives up after this many automatic restarts within `RESTART_WINDOW`.
+const MAX_RESTARTS_PER_WINDOW: usize = 5;
+const RESTART_WINDOW: Duration = Duration::from_secs(60);
+const DEFAULT_DEPTH: u32 = 17;
+const DEFAULT_THINK_TIME_MS: u64 = 100;
+/// The threat search only needs to spot the opponent's idea, so it is kept shallow.
+const DEFAULT_THREAT_DEPTH: u32 = 10;
+const DEFAULT_THREAT_THINK_TIME_MS: u64 = 50;
+/// Directory under the data dir that holds the on-disk analysis cache.
+const DISK_CACHE_DIR: &str = "analysis-cache";
+
 #[derive(Debug, Clone)]
 pub enum EngineStatus {
     NotConfigured,
     Starting,
     Ready,
     Analyzing,
+    InfiniteAnalysis,
     Restarting,
     Crashed,
     Error,
@@ -40,6 +88,7 @@ impl EngineStatus {
             EngineStatus::Starting => "starting",
             EngineStatus::Ready => "ready",
             EngineStatus::Analyzing => "analyzing",
+            EngineStatus::InfiniteAnalysis => "infinite_analysis",
             EngineStatus::Restarting => "restarting",
             EngineStatus::Crashed => "crashed",
             EngineStatus::Error => "error",
@@ -61,8 +110,33 @@ pub enum EngineManagerError {
     Installer(#[from] installer::InstallerError),
     #[error("engine task failed: {0}")]
     Join(String),
-    #[error("engine lock is poisoned")]
-    LockPoisoned,
+    #[error("{0}")]
+    EngineBusy(String),
+    #[error("no infinite analysis session is running")]
+    NoAnalysisSession,
+    #[error("analysis {request_id} was cancelled")]
+    AnalysisCancelled {
+        request_id: String,
+        /// Lines found before the search stopped.
+        lines: Vec<AnalysisLine>,
+    },
+    #[error("no analysis or job has id {0}")]
+    JobNotFound(String),
+    #[error("engine worker stopped")]
+    WorkerStopped,
+    #[error("Stockfish crashed {0} times within a minute; automatic restarts are paused. Restart the engine manually.")]
+    RestartLimitReached(usize),
+    #[error("analysis cache error: {0}")]
+    CacheStorage(#[from] std::io::Error),
+    #[error("could not read opening book {path}: {source}")]
+    OpeningBook {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("tablebase probe failed: {0}")]
+    Tablebase(String),
+    #[error("{0}")]
+    NotInTablebase(String),
 }
 
 impl EngineManagerError {
@@ -86,11 +160,33 @@ impl EngineManagerError {
                 "engine_timeout",
                 self.to_string(),
             ),
+            EngineManagerError::Stockfish(StockfishError::InvalidOption(message)) => {
+                (StatusCode::BAD_REQUEST, "invalid_request", message.clone())
+            }
             EngineManagerError::Stockfish(StockfishError::EngineExited) => (
                 StatusCode::SERVICE_UNAVAILABLE,
                 "engine_exited",
                 self.to_string(),
             ),
+            EngineManagerError::EngineBusy(message) => {
+                (StatusCode::CONFLICT, "engine_busy", message.clone())
+            }
+            EngineManagerError::NoAnalysisSession => (
+                StatusCode::NOT_FOUND,
+                "no_analysis_session",
+                self.to_string(),
+            ),
+            EngineManagerError::AnalysisCancelled { .. } => {
+                (StatusCode::CONFLICT, "analysis_cancelled", self.to_string())
+            }
+            EngineManagerError::JobNotFound(_) => {
+                (StatusCode::NOT_FOUND, "job_not_found", self.to_string())
+            }
+            EngineManagerError::RestartLimitReached(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "engine_crashed",
+                self.to_string(),
+            ),
             EngineManagerError::Stockfish(_) => {
                 (StatusCode::BAD_GATEWAY, "engine_error", self.to_string())
             }
@@ -99,7 +195,25 @@ impl EngineManagerError {
                 "stockfish_download_failed",
                 self.to_string(),
             ),
-            EngineManagerError::Join(_) | EngineManagerError::LockPoisoned => (
+            EngineManagerError::CacheStorage(_) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "cache_storage_error",
+                self.to_string(),
+            ),
+            EngineManagerError::OpeningBook { .. } => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "opening_book_error",
+                self.to_string(),
+            ),
+            EngineManagerError::Tablebase(_) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "tablebase_error",
+                self.to_string(),
+            ),
+            EngineManagerError::NotInTablebase(_) => {
+                (StatusCode::NOT_FOUND, "not_in_tablebase", self.to_string())
+            }
+            EngineManagerError::Join(_) | EngineManagerError::WorkerStopped => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "internal_engine_error",
                 self.to_string(),
@@ -110,61 +224,96 @@ impl EngineManagerError {
 
 #[derive(Clone)]
 pub struct EngineManager {
-    inner: Arc<Mutex<ManagedEngine>>,
-    cancel_flag: Arc<AtomicBool>,
+    pool: Arc<EnginePool>,
+    jobs: Arc<JobRegistry>,
+    job_store: Arc<JobStore>,
+    cache: Arc<Mutex<AnalysisCache>>,
+    /// Opened on first use, so creating a manager touches no files.
+    disk_cache: Arc<Mutex<Option<DiskCache>>>,
+    books: Arc<Mutex<BookShelf>>,
+    tablebases: Arc<Mutex<Tablebases>>,
+    /// Read on first use from the bundled table and the data dir.
+    openings: Arc<OnceLock<OpeningTable>>,
+    /// Set once `spawn_supervisor` ran, so workers added later are watched too.
+    supervising: Arc<AtomicBool>,
     install_lock: Arc<tokio::sync::Mutex<()>>,
+    recent_restarts: Arc<tokio::sync::Mutex<VecDeque<Instant>>>,
     config_store: ConfigStore,
 }
 
-struct ManagedEngine {
-    process: Option<StockfishProcess>,
-    status: EngineStatus,
-    stockfish_path: Option<PathBuf>,
-    name: Option<String>,
-    last_error: Option<String>,
-    current_job_id: Option<String>,
-}
-
 impl EngineManager {
     pub fn new(config_store: ConfigStore) -> Self {
         Self {
-            inner: Arc::new(Mutex::new(ManagedEngine {
-                process: None,
-                status: EngineStatus::NotConfigured,
-                stockfish_path: None,
-                name: None,
-                last_error: None,
-                current_job_id: None,
-            })),
-            cancel_flag: Arc::new(AtomicBool::new(false)),
+            pool: Arc::new(EnginePool::new()),
+            jobs: Arc::new(JobRegistry::new()),
+            job_store: Arc::new(JobStore::new()),
+            cache: Arc::new(Mutex::new(AnalysisCache::new())),
+            disk_cache: Arc::new(Mutex::new(None)),
+            books: Arc::new(Mutex::new(BookShelf::new())),
+            tablebases: Arc::new(Mutex::new(Tablebases::new())),
+            openings: Arc::new(OnceLock::new()),
+            supervising: Arc::new(AtomicBool::new(false)),
             install_lock: Arc::new(tokio::sync::Mutex::new(())),
+            recent_restarts: Arc::new(tokio::sync::Mutex::new(VecDeque::new())),
             config_store,
         }
     }
 
     pub async fn initialize_from_config(&self) -> Result<(), EngineManagerError> {
         let config = self.config_store.load_or_default();
-        let data_dir = self.config_store.data_dir();
-        let path = installer::detect_stockfish(config.engine.stockfish_path.as_deref(), &data_dir)
-            .ok_or(EngineManagerError::NotConfigured)?;
-
-        self.start_with_path(path, &config).await
+        let launch = self.configured_launch(&config)?;
+        self.start_with_path(launch, &config).await
     }
 
     pub async fn restart(&self) -> Result<(), EngineManagerError> {
         let config = self.config_store.load_or_default();
-        let path = {
-            let inner = self
-                .inner
-                .lock()
-                .map_err(|_| EngineManagerError::LockPoisoned)?;
-            inner
-                .stockfish_path
-                .clone()
-                .or_else(|| config.engine.stockfish_path.as_ref().map(PathBuf::from))
+        let launch = match self.primary().snapshot().launch {
+            Some(launch) => launch,
+            None => self.configured_launch(&config)?,
         };
-        let path = path.ok_or(EngineManagerError::NotConfigured)?;
-        self.start_with_path(path, &config).await
+        self.start_with_path(launch, &config).await
+    }
+
+    /// The first worker's actor. It runs infinite analysis and its engine
+    /// speaks for the whole pool in `status` and the option catalogue.
+    fn primary(&self) -> EngineActor {
+        self.pool.primary().actor.clone()
+    }
+
+    /// Switches to the named engine profile, or back to Stockfish for `None`.
+    ///
+    /// The setting is only saved once the new engine has started; otherwise
+    /// the current engine keeps running.
+    pub async fn use_profile(&self, profile: Option<String>) -> Result<(), EngineManagerError> {
+        let mut config = self.config_store.load_or_default();
+        config.engine.active_profile = profile;
+        config
+            .validate()
+            .map_err(EngineManagerError::InvalidRequest)?;
+
+        let launch = self.configured_launch(&config)?;
+        self.start_with_path(launch, &config).await?;
+        self.config_store
+            .save(&config)
+            .map_err(|err| EngineManagerError::InvalidRequest(err.to_string()))
+    }
+
+    /// The active profile's launch settings, or the detected Stockfish.
+    fn configured_launch(&self, config: &AppConfig) -> Result<EngineLaunch, EngineManagerError> {
+        if let Some(profile) = config.active_profile() {
+            return Ok(EngineLaunch {
+                path: PathBuf::from(&profile.path),
+                args: profile.args.clone(),
+                working_dir: profile.working_dir.as_ref().map(PathBuf::from),
+            });
+        }
+
+        installer::detect_stockfish(
+            config.engine.stockfish_path.as_deref(),
+            &self.config_store.data_dir(),
+        )
+        .map(EngineLaunch::from_path)
+        .ok_or(EngineManagerError::NotConfigured)
     }
 
     pub async fn detect(&self) -> Option<PathBuf> {
@@ -195,10 +344,12 @@ impl EngineManager {
 
         let mut config = self.config_store.load_or_default();
         config.engine.stockfish_path = Some(path.display().to_string());
+        config.engine.active_profile = None;
         self.config_store
             .save(&config)
             .map_err(|err| EngineManagerError::InvalidRequest(err.to_string()))?;
-        self.start_with_path(path.clone(), &config).await?;
+        self.start_with_path(EngineLaunch::from_path(&path), &config)
+            .await?;
         Ok(path)
     }
 
@@ -210,18 +361,21 @@ impl EngineManager {
         config
             .validate()
             .map_err(EngineManagerError::InvalidRequest)?;
+        let snapshot = self.primary().snapshot();
+        // A different profile means a different engine, so its options can
+        // only be checked once it has started.
+        let switching_profile = config.engine.active_profile != snapshot.profile;
+        if !switching_profile && !snapshot.options.is_empty() {
+            check_uci_options(&snapshot.options, &config)?;
+        }
         self.config_store
             .save(&config)
             .map_err(|err| EngineManagerError::InvalidRequest(err.to_string()))?;
 
-        if restart_engine {
-            let path = config
-                .engine
-                .stockfish_path
-                .as_ref()
-                .map(PathBuf::from)
-                .ok_or(EngineManagerError::NotConfigured)?;
-            self.start_with_path(path, &config).await?;
+        let resizing = usize::from(config.engine.workers) != self.pool.workers().len();
+        if restart_engine || switching_profile || resizing {
+            let launch = self.configured_launch(&config)?;
+            self.start_with_path(launch, &config).await?;
         } else {
             self.apply_engine_options(&config).await?;
         }
@@ -232,210 +386,1259 @@ impl EngineManager {
     pub async fn analyze(
         &self,
         request: AnalyzeRequest,
+    ) -> Result<AnalyzeResponse, EngineManagerError> {
+        self.analyze_inner(request, None, None).await
+    }
+
+    /// Like [`EngineManager::analyze`], but also sends every scored `info` line
+    /// to `progress` as soon as the engine prints it.
+    pub async fn analyze_with_progress(
+        &self,
+        request: AnalyzeRequest,
+        progress: UnboundedSender<AnalysisLine>,
+    ) -> Result<AnalyzeResponse, EngineManagerError> {
+        self.analyze_inner(request, Some(progress), None).await
+    }
+
+    /// Analyses every position of a batch, as many at a time as there are
+    /// engine workers, and answers them in the order they were sent.
+    ///
+    /// A position that fails only fails its own item. With `items`, each
+    /// item is sent there as soon as it and those before it are done, and
+    /// the response's `results` stay empty; the batch stops once `items` is
+    /// closed.
+    pub async fn analyze_batch(
+        &self,
+        request: BatchAnalyzeRequest,
+        items: Option<UnboundedSender<BatchItem>>,
+    ) -> Result<BatchAnalyzeResponse, EngineManagerError> {
+        let config = self.config_store.load_or_default();
+        validate_analyze_params(
+            request.depth.unwrap_or(DEFAULT_DEPTH),
+            request.max_think_time_ms.unwrap_or(DEFAULT_THINK_TIME_MS),
+        )?;
+        let batch_id = request
+            .batch_id
+            .clone()
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let requests = batch::requests(request)?;
+        let total = requests.len();
+        let guard = Arc::new(self.register_job(&batch_id, &config)?);
+        let started = Instant::now();
+
+        let queue = Arc::new(Mutex::new(
+            requests.into_iter().enumerate().collect::<VecDeque<_>>(),
+        ));
+        let (finished, mut done) = tokio::sync::mpsc::unbounded_channel();
+        for _ in 0..usize::from(config.engine.workers).clamp(1, total) {
+            let (manager, queue, finished, guard) =
+                (self.clone(), queue.clone(), finished.clone(), guard.clone());
+            let batch_id = batch_id.clone();
+            tokio::spawn(async move {
+                loop {
+                    let next = queue
+                        .lock()
+                        .unwrap_or_else(|err| err.into_inner())
+                        .pop_front();
+                    let Some((index, (id, request))) = next else {
+                        break;
+                    };
+                    // Cache hits and finished games never look at the guard.
+                    let result = if guard.is_cancelled() {
+                        Err(EngineManagerError::AnalysisCancelled {
+                            request_id: batch_id.clone(),
+                            lines: Vec::new(),
+                        })
+                    } else {
+                        manager.analyze_inner(request, None, Some(&guard)).await
+                    };
+                    if finished.send(BatchItem::new(index, id, result)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(finished);
+
+        let (mut results, mut waiting) = (Vec::new(), BTreeMap::new());
+        let (mut next, mut succeeded) = (0, 0);
+        while let Some(item) = done.recv().await {
+            waiting.insert(item.index, item);
+            while let Some(item) = waiting.remove(&next) {
+                next += 1;
+                succeeded += usize::from(item.error.is_none());
+                match &items {
+                    Some(items) => {
+                        if items.send(item).is_err() {
+                            guard.cancel();
+                            return Err(EngineManagerError::AnalysisCancelled {
+                                request_id: batch_id,
+                                lines: Vec::new(),
+                            });
+                        }
+                    }
+                    None => results.push(item),
+                }
+            }
+        }
+
+        Ok(BatchAnalyzeResponse {
+            ok: true,
+            batch_id,
+            total,
+            succeeded,
+            failed: total - succeeded,
+            time_taken_ms: started.elapsed().as_millis(),
+            results,
+        })
+    }
+
+    async fn analyze_inner(
+        &self,
+        request: AnalyzeRequest,
+        progress: Option<UnboundedSender<AnalysisLine>>,
+        job: Option<&JobGuard>,
+    ) -> Result<AnalyzeResponse, EngineManagerError> {
+        let threat_budget = if request.include_threat.unwrap_or(false) {
+            let depth = request.threat_depth.unwrap_or(DEFAULT_THREAT_DEPTH);
+            let max_think_time_ms = request
+                .threat_think_time_ms
+                .unwrap_or(DEFAULT_THREAT_THINK_TIME_MS);
+            validate_analyze_params(depth, max_think_time_ms)?;
+            Some((depth, max_think_time_ms))
+        } else {
+            None
+        };
+
+        let mut response = self.analyze_position(request, progress, job).await?;
+        if let Some((depth, max_think_time_ms)) = threat_budget {
+            response.threat = self
+                .find_threat(&response, depth, max_think_time_ms, job)
+                .await;
+        }
+        Ok(response)
+    }
+
+    async fn analyze_position(
+        &self,
+        request: AnalyzeRequest,
+        progress: Option<UnboundedSender<AnalysisLine>>,
+        job: Option<&JobGuard>,
     ) -> Result<AnalyzeResponse, EngineManagerError> {
         let request_id = request
             .request_id
             .clone()
             .unwrap_or_else(|| Uuid::new_v4().to_string());
         let config = self.config_store.load_or_default();
-        let depth = request.depth.unwrap_or(17);
-        let max_think_time_ms = request.max_think_time_ms.unwrap_or(100);
+        let depth = request.depth.unwrap_or(DEFAULT_DEPTH);
+        let max_think_time_ms = request.max_think_time_ms.unwrap_or(DEFAULT_THINK_TIME_MS);
         let disregard_think_time = request.disregard_think_time.unwrap_or(false);
 
         validate_analyze_params(depth, max_think_time_ms)?;
-        let features = position_features(&request.fen)?;
+        let mut features = request_position(
+            request.fen.as_deref(),
+            request.start_fen.as_deref(),
+            &request.moves,
+        )?;
+        features.opening = self.openings().classify(&features.position);
+
+        let engine = self.primary().snapshot();
+        if let Some(game_over) = features.game_over {
+            return Ok(AnalyzeResponse {
+                ok: true,
+                request_id,
+                fen: features.fen,
+                best_move: None,
+                ponder: None,
+                best_move_detail: None,
+                ponder_detail: None,
+                fen_after_best_move: None,
+                game_over: Some(game_over),
+                depth,
+                time_taken_ms: 0,
+                difficulty: None,
+                lines: Vec::new(),
+                totals: Default::default(),
+                engine: EngineSummary {
+                    name: engine.name,
+                    status: engine.status.as_str().to_string(),
+                },
+                cached: false,
+                source: AnalysisSource::GameOver,
+                book_moves: None,
+                tablebase: None,
+                threat: None,
+                opening: features.opening,
+                position_report: features.report,
+            });
+        }
 
-        if config.analysis.cancel_previous_on_new_request {
-            self.cancel();
+        let book_config = &config.analysis.book;
+        if request.use_book.unwrap_or(book_config.enabled)
+            && !book_config.paths.is_empty()
+            && book::game_ply(&features.board) < book_config.max_ply
+        {
+            // Like the tablebases below, a broken book falls back to the engine.
+            match self.probe_books(&features.board, &config).await {
+                Ok(Some(hit)) => {
+                    let summary = EngineSummary {
+                        name: engine.name,
+                        status: engine.status.as_str().to_string(),
+                    };
+                    let response = book_response(request_id, features, hit, summary, &config);
+                    if let Some(progress) = &progress {
+                        for line in &response.lines {
+                            let _ = progress.send(line.clone());
+                        }
+                    }
+                    return Ok(response);
+                }
+                Ok(None) => {}
+                Err(err) => tracing::warn!(%request_id, %err, "opening book probe failed"),
+            }
         }
-        self.cancel_flag.store(false, Ordering::SeqCst);
 
-        let inner = self.inner.clone();
-        let cancel_flag = self.cancel_flag.clone();
-        let fen = request.fen.clone();
-        let request_id_for_task = request_id.clone();
+        if request.use_tablebase.unwrap_or(true) && !config.engine.syzygy_paths.is_empty() {
+            // A broken table should not stop the engine from answering.
+            match self.probe_tablebases(&features.board, &config).await {
+                Ok(Some(probe)) => {
+                    let summary = EngineSummary {
+                        name: engine.name,
+                        status: engine.status.as_str().to_string(),
+                    };
+                    let response =
+                        tablebase_response(request_id, features, probe, summary, &config);
+                    if let Some(progress) = &progress {
+                        for line in &response.lines {
+                            let _ = progress.send(line.clone());
+                        }
+                    }
+                    return Ok(response);
+                }
+                Ok(None) => {}
+                Err(err) => tracing::warn!(%request_id, %err, "tablebase probe failed"),
+            }
+        }
 
-        let result = tokio::task::spawn_blocking(move || {
-            let mut inner = inner.lock().map_err(|_| EngineManagerError::LockPoisoned)?;
-            inner.current_job_id = Some(request_id_for_task);
-            inner.status = EngineStatus::Analyzing;
-            inner.last_error = None;
+        let registered;
+        let job = match job {
+            Some(job) => job,
+            None => {
+                registered = self.register_job(&request_id, &config)?;
+                &registered
+            }
+        };
 
-            let process = inner
-                .process
-                .as_mut()
-                .ok_or(EngineManagerError::NotConfigured)?;
-            process.new_game()?;
+        let cache_key = CacheKey {
+            zobrist: features.zobrist,
+            history: features.history,
+            movetime_ms: (!disregard_think_time).then_some(max_think_time_ms),
+            multipv: config.engine.multipv,
+            engine: engine_identity(&engine, &config),
+        };
+        if let Some(mut cached) = self.cached(&cache_key, depth, &config).await {
+            // The hash ignores move counters, so the move numbers are redone.
+            for line in &mut cached.raw.lines {
+                notation::annotate_line(&features.board, line);
+            }
+            if let Some(progress) = &progress {
+                for line in &cached.raw.lines {
+                    let _ = progress.send(line.clone());
+                }
+            }
+            let summary = EngineSummary {
+                name: cached.engine_name,
+                status: engine.status.as_str().to_string(),
+            };
+            let mut response =
+                analyze_response(request_id, features, depth, cached.raw, 0, summary, &config);
+            response.cached = true;
+            response.source = AnalysisSource::Cache;
+            return Ok(response);
+        }
 
-            let started = Instant::now();
-            let raw = process.analyze(&fen, depth, max_think_time_ms, disregard_think_time, || {
-                cancel_flag.load(Ordering::SeqCst)
+        let lease = self
+            .pool
+            .lease(Duration::from_millis(config.analysis.queue_timeout_ms))
+            .await?;
+        let worker = lease.worker();
+        job.mark_started();
+        if job.is_cancelled() {
+            return Err(EngineManagerError::AnalysisCancelled {
+                request_id,
+                lines: Vec::new(),
             });
-            let time_taken_ms = started.elapsed().as_millis();
-
-            match raw {
-                Ok(raw) => {
-                    inner.status = EngineStatus::Ready;
-                    inner.current_job_id = None;
-                    Ok((
-                        raw,
-                        time_taken_ms,
-                        inner.name.clone(),
-                        inner.status.as_str().to_string(),
-                    ))
-                }
-                Err(err) => {
-                    inner.status = EngineStatus::Error;
-                    inner.current_job_id = None;
-                    inner.last_error = Some(err.to_string());
-                    Err(EngineManagerError::Stockfish(err))
+        }
+
+        let params = SearchParams {
+            request_id: request_id.clone(),
+            position: features.position.clone(),
+            depth,
+            max_think_time_ms,
+            disregard_think_time,
+            new_game: request.new_game.unwrap_or(true),
+            searchmoves: Vec::new(),
+            cancel: job.cancel_token(),
+            progress,
+        };
+        let attempt = run_analysis(worker, params.clone()).await;
+        let outcome = match attempt {
+            Err(EngineManagerError::Stockfish(StockfishError::EngineExited))
+                if config.engine.auto_restart =>
+            {
+                self.recover_from_crash(worker).await?;
+                if !config.engine.retry_after_crash {
+                    return Err(EngineManagerError::Stockfish(StockfishError::EngineExited));
                 }
+                tracing::info!(%request_id, "retrying analysis after engine restart");
+                run_analysis(worker, params).await?
+            }
+            other => other?,
+        };
+        drop(lease);
+
+        let SearchOutcome {
+            raw,
+            time_taken_ms,
+            engine_name,
+            engine_status,
+        } = outcome;
+        if raw.cancelled {
+            return Err(EngineManagerError::AnalysisCancelled {
+                request_id,
+                lines: raw.lines,
+            });
+        }
+
+        let analysis = CachedAnalysis {
+            depth,
+            raw: raw.clone(),
+            engine_name: engine_name.clone(),
+        };
+        if depth >= config.analysis.disk_cache_min_depth && config.analysis.disk_cache_max_mb > 0 {
+            let (key, analysis) = (cache_key.clone(), analysis.clone());
+            let max_bytes = disk_cache_max_bytes(&config);
+            if let Err(err) = self
+                .with_disk_cache(move |cache| cache.insert(key, analysis, max_bytes))
+                .await
+            {
+                tracing::warn!(%err, "could not save the analysis to the disk cache");
             }
+        }
+        self.lock_cache()
+            .insert(cache_key, analysis, config.analysis.cache_size);
+
+        let summary = EngineSummary {
+            name: engine_name,
+            status: engine_status,
+        };
+        Ok(analyze_response(
+            request_id,
+            features,
+            depth,
+            raw,
+            time_taken_ms,
+            summary,
+            &config,
+        ))
+    }
+
+    /// Describes a position without the engine: material, phase, hanging and
+    /// pinned pieces, checks, captures, castling and en passant.
+    pub fn position(
+        &self,
+        request: PositionRequest,
+    ) -> Result<PositionResponse, EngineManagerError> {
+        let mut features = request_position(
+            request.fen.as_deref(),
+            request.start_fen.as_deref(),
+            &request.moves,
+        )?;
+        Ok(PositionResponse {
+            ok: true,
+            opening: self.openings().classify(&features.position),
+            fen: features.fen,
+            game_over: features.game_over.take(),
+            position_report: features.report,
         })
-        .await
-        .map_err(|err| EngineManagerError::Join(err.to_string()))??;
-
-        let (raw, time_taken_ms, engine_name, engine_status) = result;
-        let difficulty = if config.analysis.difficulty_enabled {
-            Some(difficulty::calculate(DifficultyInput {
-                legal_move_count: features.legal_move_count,
-                in_check: features.in_check,
-                lines: raw.lines.clone(),
-                candidate_threshold_cp: config.analysis.candidate_threshold_cp,
-                min_delay_ms: config.analysis.min_delay_ms,
-                max_delay_ms: config.analysis.max_delay_ms,
-            }))
+    }
+
+    /// Scores one move against the best move: a normal search of the
+    /// position, then a `searchmoves` search restricted to the move. When the
+    /// move is the engine's best move, the second search is skipped.
+    ///
+    /// Books and tablebases are left out, so both scores come from the engine.
+    pub async fn evaluate_move(
+        &self,
+        request: EvaluateMoveRequest,
+    ) -> Result<EvaluateMoveResponse, EngineManagerError> {
+        let config = self.config_store.load_or_default();
+        let depth = request.depth.unwrap_or(DEFAULT_DEPTH);
+        let max_think_time_ms = request.max_think_time_ms.unwrap_or(DEFAULT_THINK_TIME_MS);
+        let disregard_think_time = request.disregard_think_time.unwrap_or(false);
+        validate_analyze_params(depth, max_think_time_ms)?;
+        let features = request_position(
+            request.fen.as_deref(),
+            request.start_fen.as_deref(),
+            &request.moves,
+        )?;
+        let candidate = move_eval::parse_move(&features.board, &request.candidate)?;
+        let move_uci = candidate.to_uci(CastlingMode::Standard).to_string();
+        let move_detail = notation::move_detail(&features.board, candidate);
+
+        let request_id = request
+            .request_id
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let guard = self.register_job(&request_id, &config)?;
+        let started = Instant::now();
+        let analysis = self
+            .analyze_inner(
+                AnalyzeRequest {
+                    fen: request.fen,
+                    start_fen: request.start_fen,
+                    moves: request.moves,
+                    depth: Some(depth),
+                    max_think_time_ms: Some(max_think_time_ms),
+                    disregard_think_time: Some(disregard_think_time),
+                    request_id: Some(request_id.clone()),
+                    use_book: Some(false),
+                    use_tablebase: Some(false),
+                    ..Default::default()
+                },
+                None,
+                Some(&guard),
+            )
+            .await?;
+        let best_line = analysis.lines.iter().min_by_key(|line| line.rank).cloned();
+        let is_best = analysis.best_move.as_deref() == Some(move_uci.as_str());
+
+        let move_line = if is_best {
+            best_line.clone()
         } else {
-            None
+            let params = SearchParams {
+                request_id: request_id.clone(),
+                position: features.position.clone(),
+                depth,
+                max_think_time_ms,
+                disregard_think_time,
+                new_game: false,
+                searchmoves: vec![move_uci.clone()],
+                cancel: guard.cancel_token(),
+                progress: None,
+            };
+            let lease = self
+                .pool
+                .lease(Duration::from_millis(config.analysis.queue_timeout_ms))
+                .await?;
+            let raw = run_analysis(lease.worker(), params).await?.raw;
+            if raw.cancelled {
+                return Err(EngineManagerError::AnalysisCancelled {
+                    request_id,
+                    lines: raw.lines,
+                });
+            }
+            // Judging the move needs its score, so no line is an engine error.
+            let line = raw
+                .lines
+                .into_iter()
+                .find(|line| line.move_uci.as_deref() == Some(move_uci.as_str()))
+                .ok_or_else(|| StockfishError::MissingLine(move_uci.clone()))?;
+            Some(line)
         };
 
-        Ok(AnalyzeResponse {
+        let (centipawn_loss, classification) =
+            move_eval::judge(best_line.as_ref(), move_line.as_ref(), is_best);
+        Ok(EvaluateMoveResponse {
             ok: true,
             request_id,
-            best_move: raw.best_move,
-            ponder: raw.ponder,
+            fen: analysis.fen,
+            move_uci,
+            move_san: move_detail.san.clone(),
+            move_detail,
+            best_move_san: analysis
+                .best_move_detail
+                .as_ref()
+                .map(|detail| detail.san.clone()),
+            best_move: analysis.best_move,
+            best_score_cp: best_line.as_ref().and_then(|line| line.score_cp),
+            best_mate: best_line.as_ref().and_then(|line| line.mate),
+            move_score_cp: move_line.as_ref().and_then(|line| line.score_cp),
+            move_mate: move_line.as_ref().and_then(|line| line.mate),
+            centipawn_loss,
+            classification,
+            refutation: move_line
+                .as_ref()
+                .map(|line| line.pv.iter().skip(1).cloned().collect())
+                .unwrap_or_default(),
+            refutation_san: move_line
+                .as_ref()
+                .map(|line| line.pv_san.iter().skip(1).cloned().collect())
+                .unwrap_or_default(),
+            best_line,
+            move_line,
             depth,
-            time_taken_ms,
-            difficulty,
-            lines: raw.lines,
-            engine: EngineSummary {
-                name: engine_name,
-                status: engine_status,
-            },
+            time_taken_ms: started.elapsed().as_millis(),
+            engine: analysis.engine,
         })
     }
 
-    pub fn cancel(&self) {
-        self.cancel_flag.store(true, Ordering::SeqCst);
+    /// Passes the move in the analysed position and runs a short search for
+    /// the opponent. A failed search only leaves the threat out.
+    async fn find_threat(
+        &self,
+        response: &AnalyzeResponse,
+        depth: u32,
+        max_think_time_ms: u64,
+        job: Option<&JobGuard>,
+    ) -> Option<Threat> {
+        if response.game_over.is_some() {
+            return None;
+        }
+        let null_position = threat::null_move(&parse_position(&response.fen).ok()?)?;
+        let config = self.config_store.load_or_default();
+        let params = SearchParams {
+            request_id: response.request_id.clone(),
+            position: UciPosition {
+                fen: Fen::from_position(&null_position, EnPassantMode::Legal).to_string(),
+                moves: Vec::new(),
+            },
+            depth,
+            max_think_time_ms,
+            disregard_think_time: false,
+            new_game: true,
+            searchmoves: Vec::new(),
+            cancel: job.map_or_else(|| Arc::new(AtomicBool::new(false)), JobGuard::cancel_token),
+            progress: None,
+        };
+
+        let search = async {
+            let lease = self
+                .pool
+                .lease(Duration::from_millis(config.analysis.queue_timeout_ms))
+                .await?;
+            run_analysis(lease.worker(), params).await
+        };
+        match search.await {
+            Ok(outcome) if !outcome.raw.cancelled => {
+                let current_cp = response.lines.first().and_then(|line| line.score_cp);
+                threat::from_search(&null_position, &outcome.raw, current_cp)
+            }
+            Ok(_) => None,
+            Err(err) => {
+                tracing::warn!(request_id = %response.request_id, %err, "threat search failed");
+                None
+            }
+        }
     }
 
-    pub fn status(&self) -> EngineStatusResponse {
-        match self.inner.lock() {
-            Ok(inner) => EngineStatusResponse {
-                status: inner.status.as_str().to_string(),
-                name: inner.name.clone(),
-                stockfish_path: inner
-                    .stockfish_path
-                    .as_ref()
-                    .map(|path| path.display().to_string()),
-                last_error: inner.last_error.clone(),
-                current_job_id: inner.current_job_id.clone(),
-            },
-            Err(_) => EngineStatusResponse {
-                status: EngineStatus::Error.as_str().to_string(),
-                name: None,
-                stockfish_path: None,
-                last_error: Some("engine lock is poisoned".to_string()),
-                current_job_id: None,
-            },
+    /// Looks in memory first, then on disk; disk hits are kept in memory too.
+    async fn cached(
+        &self,
+        key: &CacheKey,
+        depth: u32,
+        config: &AppConfig,
+    ) -> Option<CachedAnalysis> {
+        if let Some(cached) = self.lock_cache().get(key, depth) {
+            return Some(cached);
         }
+        if config.analysis.disk_cache_max_mb == 0 {
+            return None;
+        }
+
+        let disk_key = key.clone();
+        let cached = self
+            .with_disk_cache(move |cache| cache.get(&disk_key, depth))
+            .await
+            .inspect_err(|err| tracing::warn!(%err, "could not read the disk cache"))
+            .ok()
+            .flatten()?;
+        self.lock_cache()
+            .insert(key.clone(), cached.clone(), config.analysis.cache_size);
+        Some(cached)
     }
 
-    async fn start_with_path(
+    fn openings(&self) -> &OpeningTable {
+        self.openings
+            .get_or_init(|| OpeningTable::load(&self.config_store.data_dir().join(OPENINGS_DIR)))
+    }
+
+    /// Looks the position up in the configured opening books, reading them
+    /// in a blocking task the first time or after they changed.
+    async fn probe_books(
         &self,
-        path: PathBuf,
+        board: &Chess,
         config: &AppConfig,
-    ) -> Result<(), EngineManagerError> {
-        let inner = self.inner.clone();
-        let config = config.clone();
+    ) -> Result<Option<BookHit>, EngineManagerError> {
+        let books = self.books.clone();
+        let board = board.clone();
+        let paths = config.analysis.book.paths.clone();
+        let min_weight = config.analysis.book.min_weight;
+        tokio::task::spawn_blocking(move || {
+            books
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .probe(&paths, &board, min_weight)
+        })
+        .await
+        .map_err(|err| EngineManagerError::Join(err.to_string()))?
+    }
 
+    /// Probes the Syzygy tables in `engine.syzygy_paths`, loading them in a
+    /// blocking task the first time or after a folder changed.
+    async fn probe_tablebases(
+        &self,
+        board: &Chess,
+        config: &AppConfig,
+    ) -> Result<Option<TablebaseProbe>, EngineManagerError> {
+        let tablebases = self.tablebases.clone();
+        let board = board.clone();
+        let paths = config.engine.syzygy_paths.clone();
         tokio::task::spawn_blocking(move || {
-        {
-            let mut inner = inner.lock().map_err(|_| EngineManagerError::LockPoisoned)?;
-            inner.status = if inner.process.is_some() {
-                EngineStatus::Restarting
-            } else {
-                EngineStatus::Starting
+            tablebases
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .probe(&paths, &board)
+        })
+        .await
+        .map_err(|err| EngineManagerError::Join(err.to_string()))?
+    }
+
+    /// Looks `fen` up in the tablebases, without falling back to the engine.
+    pub async fn tablebase(&self, fen: &str) -> Result<TablebaseResponse, EngineManagerError> {
+        let config = self.config_store.load_or_default();
+        let board = parse_position(fen)?;
+        if config.engine.syzygy_paths.is_empty() {
+            return Err(EngineManagerError::NotInTablebase(
+                "no Syzygy folders are configured".to_string(),
+            ));
+        }
+        if board.castles().any() {
+            return Err(EngineManagerError::NotInTablebase(
+                "positions with castling rights are not in the tablebases".to_string(),
+            ));
+        }
+
+        match self.probe_tablebases(&board, &config).await? {
+            Some(probe) => Ok(TablebaseResponse {
+                ok: true,
+                fen: Fen::from_position(&board, EnPassantMode::Legal).to_string(),
+                probe,
+            }),
+            None => {
+                let max_pieces = self.tablebase_folders().await?.max_pieces;
+                Err(EngineManagerError::NotInTablebase(format!(
+                    "the position has {} pieces; the configured tablebases cover up to {max_pieces}",
+                    board.board().occupied().count()
+                )))
+            }
+        }
+    }
+
+    /// Lists the piece-count sets found in each folder of `engine.syzygy_paths`.
+    pub async fn tablebase_folders(&self) -> Result<TablebaseFoldersResponse, EngineManagerError> {
+        let config = self.config_store.load_or_default();
+        let tablebases = self.tablebases.clone();
+        let paths = config.engine.syzygy_paths;
+        tokio::task::spawn_blocking(move || {
+            let max_pieces = tablebases
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .max_pieces(&paths);
+            TablebaseFoldersResponse {
+                ok: true,
+                max_pieces,
+                folders: paths
+                    .iter()
+                    .map(|path| tablebase::scan_folder(path))
+                    .collect(),
+            }
+        })
+        .await
+        .map_err(|err| EngineManagerError::Join(err.to_string()))
+    }
+
+    /// Runs `f` on the on-disk cache in a blocking task, opening it first if needed.
+    async fn with_disk_cache<T, F>(&self, f: F) -> Result<T, EngineManagerError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut DiskCache) -> std::io::Result<T> + Send + 'static,
+    {
+        let disk_cache = self.disk_cache.clone();
+        let dir = self.config_store.data_dir().join(DISK_CACHE_DIR);
+        tokio::task::spawn_blocking(move || {
+            let mut guard = disk_cache.lock().unwrap_or_else(|err| err.into_inner());
+            let cache = match &mut *guard {
+                Some(cache) => cache,
+                slot => slot.insert(DiskCache::open(&dir)?),
+            };
+            Ok(f(cache)?)
+        })
+        .await
+        .map_err(|err| EngineManagerError::Join(err.to_string()))?
+    }
+
+    pub async fn analysis_cache(&self) -> Result<AnalysisCacheResponse, EngineManagerError> {
+        let config = self.config_store.load_or_default();
+        let (path, entries, size_bytes, skipped_records) = self
+            .with_disk_cache(|cache| {
+                Ok((
+                    cache.dir().display().to_string(),
+                    cache.len(),
+                    cache.size_bytes(),
+                    cache.skipped_records(),
+                ))
+            })
+            .await?;
+        Ok(AnalysisCacheResponse {
+            ok: true,
+            memory_entries: self.lock_cache().len(),
+            disk: DiskCacheResponse {
+                enabled: config.analysis.disk_cache_max_mb > 0,
+                path,
+                entries,
+                size_bytes,
+                max_bytes: disk_cache_max_bytes(&config),
+                min_depth: config.analysis.disk_cache_min_depth,
+                skipped_records,
+            },
+        })
+    }
+
+    /// Empties both the in-memory and the on-disk cache.
+    pub async fn clear_analysis_cache(&self) -> Result<AnalysisCacheResponse, EngineManagerError> {
+        self.clear_cache();
+        self.with_disk_cache(|cache| cache.clear()).await?;
+        self.analysis_cache().await
+    }
+
+    fn lock_cache(&self) -> std::sync::MutexGuard<'_, AnalysisCache> {
+        self.cache.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Forgets the analyses cached in memory; called whenever the engine or its
+    /// options change. The disk cache keys entries by engine identity instead.
+    fn clear_cache(&self) {
+        self.lock_cache().clear();
+    }
+
+    /// Queues an analysis in the background and returns its job right away.
+    ///
+    /// The request is validated first, so a bad FEN or move list fails here
+    /// rather than as a failed job.
+    pub fn submit_job(
+        &self,
+        mut request: AnalyzeRequest,
+    ) -> Result<JobResponse, EngineManagerError> {
+        let config = self.config_store.load_or_default();
+        let depth = request.depth.unwrap_or(DEFAULT_DEPTH);
+        validate_analyze_params(
+            depth,
+            request.max_think_time_ms.unwrap_or(DEFAULT_THINK_TIME_MS),
+        )?;
+        request_position(
+            request.fen.as_deref(),
+            request.start_fen.as_deref(),
+            &request.moves,
+        )?;
+
+        let job_id = request
+            .request_id
+            .get_or_insert_with(|| Uuid::new_v4().to_string())
+            .clone();
+        let guard = self.register_job(&job_id, &config)?;
+        self.job_store.insert(
+            &job_id,
+            JobKind::Analysis,
+            depth,
+            Duration::from_secs(config.analysis.job_retention_secs),
+        )?;
+
+        let manager = self.clone();
+        let id = job_id.clone();
+        tokio::spawn(async move {
+            let (progress, mut lines) = tokio::sync::mpsc::unbounded_channel();
+            let store = manager.job_store.clone();
+            let line_id = id.clone();
+            let forward = tokio::spawn(async move {
+                while let Some(line) = lines.recv().await {
+                    store.record_line(&line_id, line);
+                }
+            });
+
+            let result = manager
+                .analyze_inner(request, Some(progress), Some(&guard))
+                .await;
+            let _ = forward.await;
+            manager.job_store.finish(
+                &id,
+                result.map(|response| JobResult::Analysis(Box::new(response))),
+            );
+        });
+
+        self.job(&job_id)
+    }
+
+    /// Queues a review of a whole game and returns its job right away.
+    ///
+    /// Every position is analysed in turn through the cache and the engine
+    /// pool; cancelling the job stops the review after the current search.
+    pub fn submit_review(&self, request: ReviewRequest) -> Result<JobResponse, EngineManagerError> {
+        let config = self.config_store.load_or_default();
+        let depth = request.depth.unwrap_or(DEFAULT_DEPTH);
+        let max_think_time_ms = request.max_think_time_ms.unwrap_or(DEFAULT_THINK_TIME_MS);
+        validate_analyze_params(depth, max_think_time_ms)?;
+
+        let game = match &request.pgn {
+            Some(_) if !request.moves.is_empty() || request.start_fen.is_some() => {
+                return Err(EngineManagerError::InvalidRequest(
+                    "send either pgn or start_fen and moves, not both".to_string(),
+                ))
+            }
+            Some(pgn) => review::from_pgn(pgn)?,
+            None => review::from_moves(
+                request.start_fen.as_deref().unwrap_or(STARTING_FEN),
+                &request.moves,
+            )?,
+        };
+
+        let review_id = request
+            .review_id
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let guard = self.register_job(&review_id, &config)?;
+        self.job_store.insert(
+            &review_id,
+            JobKind::Review,
+            depth,
+            Duration::from_secs(config.analysis.job_retention_secs),
+        )?;
+        self.job_store
+            .record_plies(&review_id, 0, game.positions.len());
+
+        let manager = self.clone();
+        let id = review_id.clone();
+        let disregard_think_time = request.disregard_think_time.unwrap_or(false);
+        tokio::spawn(async move {
+            let result = manager
+                .run_review(
+                    &id,
+                    &game,
+                    depth,
+                    max_think_time_ms,
+                    disregard_think_time,
+                    &guard,
+                )
+                .await;
+            manager.job_store.finish(
+                &id,
+                result.map(|review| JobResult::Review(Box::new(review))),
+            );
+        });
+
+        self.job(&review_id)
+    }
+
+    async fn run_review(
+        &self,
+        review_id: &str,
+        game: &ReviewGame,
+        depth: u32,
+        max_think_time_ms: u64,
+        disregard_think_time: bool,
+        guard: &JobGuard,
+    ) -> Result<GameReview, EngineManagerError> {
+        let started = Instant::now();
+        let total = game.positions.len();
+        let mut evals = Vec::with_capacity(total);
+
+        for ply in 0..total {
+            // Cache hits never look at the guard, so check it here as well.
+            if guard.is_cancelled() {
+                return Err(EngineManagerError::AnalysisCancelled {
+                    request_id: review_id.to_string(),
+                    lines: Vec::new(),
+                });
+            }
+            let request = AnalyzeRequest {
+                fen: None,
+                start_fen: Some(game.start_fen.clone()),
+                moves: game.moves[..ply].to_vec(),
+                depth: Some(depth),
+                max_think_time_ms: Some(max_think_time_ms),
+                disregard_think_time: Some(disregard_think_time),
+                request_id: Some(review_id.to_string()),
+                // Book moves come without a score to compare against.
+                use_book: Some(false),
+                use_tablebase: None,
+                include_threat: None,
+                threat_depth: None,
+                threat_think_time_ms: None,
+                new_game: None,
             };
-            inner.last_error = None;
-            inner.current_job_id = None;
+            evals.push(self.analyze_inner(request, None, Some(guard)).await?);
+            self.job_store.record_plies(review_id, ply + 1, total);
         }
 
-        let startup_result = (|| -> Result<StockfishProcess, EngineManagerError> {
-            let mut process = StockfishProcess::spawn(&path)?;
-            apply_options_to_process(&mut process, &config)?;
-            Ok(process)
-        })();
+        Ok(review::build(
+            review_id.to_string(),
+            game,
+            &evals,
+            depth,
+            started.elapsed().as_millis(),
+        ))
+    }
 
-        match startup_result {
-            Ok(new_process) => {
-                let name = new_process.name.clone();
+    /// Registers an analysis so it can be cancelled, first cancelling the
+    /// others when `cancel_previous_on_new_request` is on.
+    fn register_job(
+        &self,
+        request_id: &str,
+        config: &AppConfig,
+    ) -> Result<JobGuard, EngineManagerError> {
+        if config.analysis.cancel_previous_on_new_request {
+            self.cancel();
+        }
+        self.jobs.register(request_id)
+    }
 
-                let mut inner = inner.lock().map_err(|_| EngineManagerError::LockPoisoned)?;
+    pub fn job(&self, job_id: &str) -> Result<JobResponse, EngineManagerError> {
+        self.job_store
+            .response(job_id, self.jobs.is_started(job_id))
+            .ok_or_else(|| EngineManagerError::JobNotFound(job_id.to_string()))
+    }
 
-                if let Some(old_process) = inner.process.as_mut() {
-                    old_process.try_kill();
+    /// Cancels a queued or running job, or forgets a finished one.
+    pub fn delete_job(&self, job_id: &str) -> Result<JobResponse, EngineManagerError> {
+        let job = self.job(job_id)?;
+        if !self.jobs.cancel(job_id) {
+            self.job_store.remove_finished(job_id);
+        }
+        Ok(job)
+    }
+
+    /// Starts background tasks that restart a worker's engine whenever its
+    /// actor reports a crash and `engine.auto_restart` is enabled.
+    pub fn spawn_supervisor(&self) {
+        self.supervising.store(true, Ordering::SeqCst);
+        for worker in self.pool.workers() {
+            self.supervise(&worker);
+        }
+    }
+
+    fn supervise(&self, worker: &Worker) {
+        let manager = self.clone();
+        let worker_id = worker.id;
+        let mut snapshots = worker.actor.subscribe();
+        tokio::spawn(async move {
+            // Ends when the worker is removed from the pool and its actor stops.
+            while snapshots.changed().await.is_ok() {
+                let crashed = matches!(snapshots.borrow_and_update().status, EngineStatus::Crashed);
+                if !crashed || !manager.config_store.load_or_default().engine.auto_restart {
+                    continue;
+                }
+                let Some(worker) = manager.pool.find(worker_id) else {
+                    break;
+                };
+                if let Err(err) = manager.recover_from_crash(&worker).await {
+                    tracing::error!(%err, worker_id, "automatic engine restart failed");
                 }
+                // Ignore the updates recovery itself published.
+                snapshots.mark_unchanged();
+            }
+        });
+    }
+
+    /// Respawns a worker's crashed engine from the stored path with exponential
+    /// backoff, giving up once `MAX_RESTARTS_PER_WINDOW` restarts (across all
+    /// workers) happened within `RESTART_WINDOW`.
+    async fn recover_from_crash(&self, worker: &Worker) -> Result<(), EngineManagerError> {
+        let mut recent_restarts = self.recent_restarts.lock().await;
+
+        loop {
+            let config = self.config_store.load_or_default();
+            let snapshot = worker.actor.snapshot();
+            if snapshot.has_process {
+                return Ok(());
+            }
 
-                inner.process = Some(new_process);
-                inner.stockfish_path = Some(path.clone());
-                inner.name = name;
-                inner.status = EngineStatus::Ready;
-                inner.last_error = None;
-                inner.current_job_id = None;
+            let now = Instant::now();
+            while recent_restarts
+                .front()
+                .is_some_and(|at| now.duration_since(*at) > RESTART_WINDOW)
+            {
+                recent_restarts.pop_front();
+            }
 
-                Ok(())
+            let recent = recent_restarts.len();
+            if recent >= MAX_RESTARTS_PER_WINDOW {
+                let err = EngineManagerError::RestartLimitReached(recent);
+                worker.actor.update(|s| {
+                    s.status = EngineStatus::Crashed;
+                    s.last_error = Some(err.to_string());
+                });
+                return Err(err);
             }
-            Err(err) => {
-                let mut inner = inner.lock().map_err(|_| EngineManagerError::LockPoisoned)?;
-
-                if inner.process.is_some() {
-                    inner.status = EngineStatus::Ready;
-                    inner.last_error = Some(format!(
-                        "Could not switch to the new Stockfish at {}: {err}. Keeping the current engine.",
-                        path.display()
-                    ));
-                } else {
-                    inner.process = None;
-                    inner.stockfish_path = Some(path.clone());
-                    inner.name = None;
-                    inner.status = EngineStatus::Error;
-                    inner.last_error = Some(err.to_string());
-                    inner.current_job_id = None;
+
+            let launch = match snapshot.launch {
+                Some(launch) => launch,
+                None => self.configured_launch(&config)?,
+            };
+
+            recent_restarts.push_back(now);
+            worker.actor.update(|s| {
+                s.status = EngineStatus::Crashed;
+                s.restart_count += 1;
+            });
+            tokio::time::sleep(restart_backoff(recent)).await;
+
+            let started = match self
+                .spawn_processes(std::slice::from_ref(&worker.actor), &launch, &config, 1)
+                .await
+            {
+                Ok(processes) => {
+                    let profile = snapshot.profile.clone();
+                    install_processes(vec![worker.actor.clone()], processes, launch, profile).await
+                }
+                Err(err) => Err(err),
+            };
+            match started {
+                Ok(()) => {
+                    tracing::info!(worker_id = worker.id, "chess engine restarted after crash");
+                    return Ok(());
+                }
+                Err(err) => tracing::warn!(%err, "engine restart attempt failed"),
+            }
+        }
+    }
+
+    pub async fn start_infinite(
+        &self,
+        request: InfiniteAnalysisRequest,
+    ) -> Result<InfiniteAnalysisResponse, EngineManagerError> {
+        playable_position(&request.fen)?;
+        let primary = self.pool.primary();
+        if primary.is_busy() || primary.actor.snapshot().current_job_id.is_some() {
+            return Err(EngineManagerError::EngineBusy(
+                "The engine is analysing another request. Try again when it is ready.".to_string(),
+            ));
+        }
+
+        let session_id = Uuid::new_v4().to_string();
+        let fen = request.fen;
+        let session = primary
+            .actor
+            .request(|reply| EngineCommand::StartInfinite {
+                session_id,
+                fen,
+                reply,
+            })
+            .await??;
+        Ok(infinite_response(session))
+    }
+
+    pub fn infinite_session(&self) -> Result<InfiniteAnalysisResponse, EngineManagerError> {
+        self.primary()
+            .infinite_session()
+            .map(infinite_response)
+            .ok_or(EngineManagerError::NoAnalysisSession)
+    }
+
+    pub async fn set_infinite_position(
+        &self,
+        request: InfiniteAnalysisRequest,
+    ) -> Result<InfiniteAnalysisResponse, EngineManagerError> {
+        playable_position(&request.fen)?;
+        let fen = request.fen;
+        let session = self
+            .primary()
+            .request(|reply| EngineCommand::SetInfinitePosition { fen, reply })
+            .await??;
+        Ok(infinite_response(session))
+    }
+
+    pub async fn stop_infinite(&self) -> Result<InfiniteAnalysisResponse, EngineManagerError> {
+        let session = self
+            .primary()
+            .request(|reply| EngineCommand::StopInfinite { reply })
+            .await??;
+        Ok(infinite_response(session))
+    }
+
+    /// Cancels every queued or running analysis and returns their request IDs.
+    pub fn cancel(&self) -> Vec<String> {
+        self.jobs.cancel_all()
+    }
+
+    pub fn cancel_request(&self, request_id: &str) -> Result<(), EngineManagerError> {
+        if self.jobs.cancel(request_id) {
+            Ok(())
+        } else {
+            Err(EngineManagerError::JobNotFound(request_id.to_string()))
+        }
+    }
+
+    pub fn status(&self) -> EngineStatusResponse {
+        let snapshot = self.primary().snapshot();
+        let workers: Vec<WorkerStatusResponse> = self
+            .pool
+            .workers()
+            .iter()
+            .enumerate()
+            .map(|(index, worker)| {
+                let worker_snapshot = worker.actor.snapshot();
+                WorkerStatusResponse {
+                    index,
+                    status: worker_snapshot.status.as_str().to_string(),
+                    current_job_id: worker_snapshot.current_job_id,
+                    restart_count: worker_snapshot.restart_count,
+                    last_error: worker_snapshot.last_error,
                 }
+            })
+            .collect();
+        // An idle primary does not mean an idle pool.
+        let status = match snapshot.status {
+            EngineStatus::Ready if workers.iter().any(|worker| worker.status == "analyzing") => {
+                EngineStatus::Analyzing.as_str()
+            }
+            ref status => status.as_str(),
+        };
+
+        EngineStatusResponse {
+            status: status.to_string(),
+            name: snapshot.name,
+            stockfish_path: snapshot
+                .launch
+                .as_ref()
+                .map(|launch| launch.path.display().to_string()),
+            profile: snapshot.profile,
+            last_error: snapshot.last_error,
+            current_job_id: snapshot.current_job_id,
+            restart_count: workers.iter().map(|worker| worker.restart_count).sum(),
+            last_crash_reason: snapshot.last_crash_reason,
+            workers,
+        }
+    }
 
-                Err(err)
+    /// Starts `engine.workers` engines from `launch`, growing or shrinking the
+    /// pool to match. If any of them fails to start, the current engines keep
+    /// running.
+    async fn start_with_path(
+        &self,
+        launch: EngineLaunch,
+        config: &AppConfig,
+    ) -> Result<(), EngineManagerError> {
+        let size = usize::from(config.engine.workers.max(1));
+        let existing: Vec<EngineActor> = self
+            .pool
+            .workers()
+            .iter()
+            .take(size)
+            .map(|worker| worker.actor.clone())
+            .collect();
+        let processes = self
+            .spawn_processes(&existing, &launch, config, size)
+            .await?;
+
+        for worker in self.pool.resize(size) {
+            if self.supervising.load(Ordering::SeqCst) {
+                self.supervise(&worker);
             }
         }
-        })
+
+        let actors = self
+            .pool
+            .workers()
+            .iter()
+            .map(|worker| worker.actor.clone())
+            .collect();
+        self.clear_cache();
+        install_processes(
+            actors,
+            processes,
+            launch,
+            config.engine.active_profile.clone(),
+        )
         .await
-        .map_err(|err| EngineManagerError::Join(err.to_string()))?
     }
 
-    async fn apply_engine_options(&self, config: &AppConfig) -> Result<(), EngineManagerError> {
-        let inner = self.inner.clone();
-        let config = config.clone();
-        tokio::task::spawn_blocking(move || {
-            let mut inner = inner.lock().map_err(|_| EngineManagerError::LockPoisoned)?;
-            let process = inner
-                .process
-                .as_mut()
-                .ok_or(EngineManagerError::NotConfigured)?;
-            apply_options_to_process(process, &config)?;
-            Ok(())
-        })
+    /// Launches `count` engines with their share of the configured resources,
+    /// reporting progress and failures on `actors`.
+    async fn spawn_processes(
+        &self,
+        actors: &[EngineActor],
+        launch: &EngineLaunch,
+        config: &AppConfig,
+        count: usize,
+    ) -> Result<Vec<StockfishProcess>, EngineManagerError> {
+        for actor in actors {
+            actor.update(|s| {
+                s.status = if s.has_process {
+                    EngineStatus::Restarting
+                } else {
+                    EngineStatus::Starting
+                };
+                s.last_error = None;
+            });
+        }
+
+        let spawn_launch = launch.clone();
+        let worker_config = worker_config(config);
+        let startup_result = tokio::task::spawn_blocking(
+            move || -> Result<Vec<StockfishProcess>, EngineManagerError> {
+                (0..count)
+                    .map(|_| {
+                        let mut process = StockfishProcess::launch(&spawn_launch)?;
+                        apply_options_to_process(&mut process, &worker_config)?;
+                        Ok(process)
+                    })
+                    .collect()
+            },
+        )
         .await
-        .map_err(|err| EngineManagerError::Join(err.to_string()))?
+        .map_err(|err| EngineManagerError::Join(err.to_string()))?;
+
+        startup_result.inspect_err(|err| {
+            let profile = config.engine.active_profile.clone();
+            for actor in actors {
+                actor.update(|s| {
+                    if s.has_process {
+                        s.status = s.running_status();
+                        s.last_error = Some(format!(
+                            "Could not switch to the new engine at {}: {err}. Keeping the current engine.",
+                            launch.path.display()
+                        ));
+                    } else {
+                        s.launch = Some(launch.clone());
+                        s.profile = profile.clone();
+                        s.name = None;
+                        s.status = EngineStatus::Error;
+                        s.last_error = Some(err.to_string());
+                        s.current_job_id = None;
+                    }
+                });
+            }
+        })
+    }
+
+    /// The option catalogue of the current (or last crashed) engine.
+    pub fn engine_options(&self) -> Result<Vec<UciOption>, EngineManagerError> {
+        let snapshot = self.primary().snapshot();
+        if !snapshot.has_process && snapshot.options.is_empty() {
+            return Err(EngineManagerError::NotConfigured);
+        }
+        Ok(snapshot.options)
+    }
+
+    async fn apply_engine_options(&self, config: &AppConfig) -> Result<(), EngineManagerError> {
+        let config = worker_config(config);
+        self.clear_cache();
+        for worker in self.pool.workers() {
+            let config = config.clone();
+            worker
+                .actor
+                .request(|reply| EngineCommand::ApplyOptions { config, reply })
+                .await??;
+        }
+        Ok(())
     }
 
     pub async fn detect_stockfish(&self) -> Result<Option<std::path::PathBuf>, String> {
@@ -515,12 +1718,13 @@ impl EngineManager {
             .map_err(|err| format!("Could not load settings: {err}"))?;
 
         config.engine.stockfish_path = Some(path.display().to_string());
+        config.engine.active_profile = None;
 
         self.config_store
             .save(&config)
             .map_err(|err| format!("Could not save Stockfish path: {err}"))?;
 
-        self.start_with_path(path.clone(), &config)
+        self.start_with_path(EngineLaunch::from_path(&path), &config)
             .await
             .map_err(|err| format!("Stockfish was saved, but could not be started: {err}"))?;
 
@@ -528,13 +1732,218 @@ impl EngineManager {
     }
 }
 
-fn apply_options_to_process(
+/// Builds the `/analyze` response for a search result, fresh or cached.
+fn analyze_response(
+    request_id: String,
+    features: PositionFeatures,
+    depth: u32,
+    raw: RawAnalysisResult,
+    time_taken_ms: u128,
+    engine: EngineSummary,
+    config: &AppConfig,
+) -> AnalyzeResponse {
+    let difficulty = if config.analysis.difficulty_enabled {
+        Some(difficulty::calculate(DifficultyInput {
+            legal_move_count: features.legal_move_count,
+            in_check: features.in_check,
+            lines: raw.lines.clone(),
+            candidate_threshold_cp: config.analysis.candidate_threshold_cp,
+            min_delay_ms: config.analysis.min_delay_ms,
+            max_delay_ms: config.analysis.max_delay_ms,
+        }))
+    } else {
+        None
+    };
+
+    let board = &features.board;
+    let ponder_detail = notation::play(board, &raw.best_move)
+        .zip(raw.ponder.as_deref())
+        .and_then(|(after, ponder)| notation::describe_move(&after, ponder));
+
+    AnalyzeResponse {
+        ok: true,
+        request_id,
+        best_move_detail: notation::describe_move(board, &raw.best_move),
+        ponder_detail,
+        fen_after_best_move: notation::fen_after(board, &raw.best_move),
+        fen: features.fen,
+        best_move: Some(raw.best_move),
+        ponder: raw.ponder,
+        game_over: None,
+        depth,
+        time_taken_ms,
+        difficulty,
+        lines: raw.lines,
+        totals: raw.totals,
+        engine,
+        cached: false,
+        source: AnalysisSource::Engine,
+        book_moves: None,
+        tablebase: None,
+        threat: None,
+        opening: features.opening,
+        position_report: features.report,
+    }
+}
+
+/// Answers from an opening book: one line per book move, without scores,
+/// and the heaviest move as the best move.
+fn book_response(
+    request_id: String,
+    features: PositionFeatures,
+    hit: BookHit,
+    engine: EngineSummary,
+    config: &AppConfig,
+) -> AnalyzeResponse {
+    let lines = hit
+        .moves
+        .iter()
+        .enumerate()
+        .map(|(index, book_move)| {
+            let mut line = AnalysisLine {
+                rank: index as u8 + 1,
+                move_uci: Some(book_move.uci.clone()),
+                pv: vec![book_move.uci.clone()],
+                ..Default::default()
+            };
+            notation::annotate_line(&features.board, &mut line);
+            line
+        })
+        .collect();
+    let raw = RawAnalysisResult {
+        best_move: hit.moves[0].uci.clone(),
+        ponder: None,
+        lines,
+        totals: Default::default(),
+        cancelled: false,
+    };
+
+    tracing::debug!(%request_id, path = %hit.path, "answered from the opening book");
+    let mut response = analyze_response(request_id, features, 0, raw, 0, engine, config);
+    response.source = AnalysisSource::Book;
+    response.book_moves = Some(hit.moves);
+    response
+}
+
+/// Answers from the tablebases: one scored line per move, best first, up to
+/// `engine.multipv` lines.
+fn tablebase_response(
+    request_id: String,
+    features: PositionFeatures,
+    probe: TablebaseProbe,
+    engine: EngineSummary,
+    config: &AppConfig,
+) -> AnalyzeResponse {
+    let white_to_move = features.board.turn().is_white();
+    let lines = probe
+        .moves
+        .iter()
+        .take(usize::from(config.engine.multipv))
+        .enumerate()
+        .map(|(index, tb_move)| {
+            let mut line = AnalysisLine {
+                rank: index as u8 + 1,
+                move_uci: Some(tb_move.uci.clone()),
+                score_cp: Some(tb_move.wdl.score_cp(tb_move.dtz)),
+                wdl: Some(tb_move.wdl.wdl()),
+                pv: vec![tb_move.uci.clone()],
+                ..Default::default()
+            };
+            wdl::complete_line(&mut line, white_to_move);
+            notation::annotate_line(&features.board, &mut line);
+            line
+        })
+        .collect();
+    let raw = RawAnalysisResult {
+        best_move: probe.moves[0].uci.clone(),
+        ponder: None,
+        lines,
+        totals: Default::default(),
+        cancelled: false,
+    };
+
+    tracing::debug!(%request_id, pieces = probe.pieces, "answered from the tablebases");
+    let mut response = analyze_response(request_id, features, 0, raw, 0, engine, config);
+    response.source = AnalysisSource::Tablebase;
+    response.tablebase = Some(probe);
+    response
+}
+
+/// Identifies the running engine and the options that change its play, for cache keys.
+fn engine_identity(snapshot: &EngineSnapshot, config: &AppConfig) -> String {
+    let path = snapshot
+        .launch
+        .as_ref()
+        .map(|launch| launch.path.display().to_string())
+        .unwrap_or_default();
+    let options = config
+        .uci_options()
+        .iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join(";");
+    format!(
+        "{path}|{}|{options}",
+        snapshot.name.as_deref().unwrap_or_default()
+    )
+}
+
+fn disk_cache_max_bytes(config: &AppConfig) -> u64 {
+    config.analysis.disk_cache_max_mb * 1024 * 1024
+}
+
+async fn run_analysis(
+    worker: &Worker,
+    params: SearchParams,
+) -> Result<SearchOutcome, EngineManagerError> {
+    worker
+        .actor
+        .request(|reply| EngineCommand::Analyze { params, reply })
+        .await?
+}
+
+/// Hands freshly started engines to their actors, one per actor.
+async fn install_processes(
+    actors: Vec<EngineActor>,
+    processes: Vec<StockfishProcess>,
+    launch: EngineLaunch,
+    profile: Option<String>,
+) -> Result<(), EngineManagerError> {
+    for (actor, process) in actors.into_iter().zip(processes) {
+        let launch = launch.clone();
+        let profile = profile.clone();
+        actor
+            .request(|reply| EngineCommand::Install {
+                process,
+                launch,
+                profile,
+                reply,
+            })
+            .await?;
+    }
+    Ok(())
+}
+
+/// The configuration each worker's engine gets: an equal share of `Hash` and
+/// `Threads`, at least 1 MB and one thread each.
+fn worker_config(config: &AppConfig) -> AppConfig {
+    let workers = u32::from(config.engine.workers.max(1));
+    let mut worker_config = config.clone();
+    worker_config.engine.hash_mb = (config.engine.hash_mb / workers).max(1);
+    worker_config.engine.threads = (config.engine.threads / workers).max(1);
+    worker_config
+}
+
+pub(crate) fn apply_options_to_process(
     process: &mut StockfishProcess,
     config: &AppConfig,
 ) -> Result<(), StockfishError> {
-    process.set_option("Hash", &config.engine.hash_mb.to_string())?;
-    process.set_option("Threads", &config.engine.threads.to_string())?;
-    process.set_option("MultiPV", &config.engine.multipv.to_string())?;
+    set_managed_option(process, "Hash", &config.engine.hash_mb.to_string())?;
+    set_managed_option(process, "Threads", &config.engine.threads.to_string())?;
+    set_managed_option(process, "MultiPV", &config.engine.multipv.to_string())?;
+    if process.supports_option("UCI_ShowWDL") {
+        process.set_option("UCI_ShowWDL", "true")?;
+    }
 
     if !config.engine.syzygy_paths.is_empty() {
         let joined = config
@@ -545,12 +1954,72 @@ fn apply_options_to_process(
             } else {
                 ":"
             });
-        process.set_option("SyzygyPath", &joined)?;
+        set_managed_option(process, "SyzygyPath", &joined)?;
+    }
+
+    let options = config.uci_options();
+    check_uci_options(process.options(), config)?;
+    for (name, value) in &options {
+        process.set_option(name, value)?;
     }
 
     Ok(())
 }
 
+/// Skips options the engine does not advertise, e.g. `Hash` on Lc0. Engines
+/// that list no options at all still get every setting.
+fn set_managed_option(
+    process: &mut StockfishProcess,
+    name: &str,
+    value: &str,
+) -> Result<(), StockfishError> {
+    if process.options().is_empty() || process.supports_option(name) {
+        process.set_option(name, value)?;
+    }
+    Ok(())
+}
+
+fn check_uci_options(catalogue: &[UciOption], config: &AppConfig) -> Result<(), StockfishError> {
+    for (name, value) in &config.uci_options() {
+        let option = catalogue
+            .iter()
+            .find(|option| option.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                StockfishError::InvalidOption(format!("the engine has no option named {name}"))
+            })?;
+        option
+            .check_value(value)
+            .map_err(StockfishError::InvalidOption)?;
+    }
+    Ok(())
+}
+
+fn infinite_response(session: InfiniteSession) -> InfiniteAnalysisResponse {
+    let elapsed = session
+        .stopped_at
+        .unwrap_or_else(Instant::now)
+        .saturating_duration_since(session.started_at);
+
+    InfiniteAnalysisResponse {
+        ok: true,
+        session_id: session.session_id,
+        fen: session.fen,
+        running: session.stopped_at.is_none(),
+        elapsed_ms: elapsed.as_millis(),
+        depth: session.lines.iter().filter_map(|line| line.depth).max(),
+        lines: session.lines,
+        best_move: session.best_move,
+        ponder: session.ponder,
+    }
+}
+
+fn restart_backoff(recent_restarts: usize) -> Duration {
+    let factor = 1_u32 << recent_restarts.min(16);
+    RESTART_BACKOFF_BASE
+        .saturating_mul(factor)
+        .min(RESTART_BACKOFF_MAX)
+}
+
 fn validate_analyze_params(depth: u32, max_think_time_ms: u64) -> Result<(), EngineManagerError> {
     if !(1..=40).contains(&depth) {
         return Err(EngineManagerError::InvalidRequest(
@@ -565,19 +2034,125 @@ fn validate_analyze_params(depth: u32, max_think_time_ms: u64) -> Result<(), Eng
     Ok(())
 }
 
+/// FEN of the standard starting position, used when `moves` come without a `start_fen`.
+pub(crate) const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
 struct PositionFeatures {
     legal_move_count: usize,
     in_check: bool,
+    game_over: Option<GameOver>,
+    zobrist: u64,
+    /// [`cache::history_hash`] of the positions reached through `moves`.
+    history: u64,
+    /// What the engine is sent: the start FEN plus the validated moves.
+    position: UciPosition,
+    /// FEN of the position reached after the moves.
+    fen: String,
+    board: Chess,
+    report: PositionReport,
+    /// Filled in by the manager, which owns the opening table.
+    opening: Option<Opening>,
+}
+
+/// Works out which position a request's `fen`, `start_fen` and `moves` describe.
+///
+/// `moves` are played from `start_fen` (or the standard start position). When
+/// both `moves` and `fen` are given, `fen` must be the position they lead to.
+fn request_position(
+    fen: Option<&str>,
+    start_fen: Option<&str>,
+    moves: &[String],
+) -> Result<PositionFeatures, EngineManagerError> {
+    let start_fen = match (start_fen, fen) {
+        (Some(start_fen), _) => start_fen,
+        (None, Some(fen)) if moves.is_empty() => fen,
+        (None, _) if !moves.is_empty() => STARTING_FEN,
+        _ => {
+            return Err(EngineManagerError::InvalidRequest(
+                "fen is required unless moves are given".to_string(),
+            ))
+        }
+    };
+
+    let features = position_features(start_fen, moves)?;
+
+    if let Some(fen) = fen {
+        if fen != start_fen && epd(fen)? != epd(&features.fen)? {
+            return Err(EngineManagerError::InvalidRequest(format!(
+                "fen does not match the position reached after moves ({})",
+                features.fen
+            )));
+        }
+    }
+
+    Ok(features)
 }
 
-fn position_features(fen: &str) -> Result<PositionFeatures, EngineManagerError> {
+/// Infinite analysis needs a position the engine can actually search.
+fn playable_position(fen: &str) -> Result<(), EngineManagerError> {
+    match position_features(fen, &[])?.game_over {
+        Some(game_over) => Err(EngineManagerError::InvalidRequest(format!(
+            "the game is already over by {}",
+            game_over.describe()
+        ))),
+        None => Ok(()),
+    }
+}
+
+pub(crate) fn parse_position(fen: &str) -> Result<Chess, EngineManagerError> {
     let fen: Fen = fen.parse().map_err(|_| EngineManagerError::InvalidFen)?;
-    let position: Chess = fen
-        .into_position(CastlingMode::Standard)
-        .map_err(|_| EngineManagerError::InvalidFen)?;
+    fen.into_position(CastlingMode::Standard)
+        .map_err(|_| EngineManagerError::InvalidFen)
+}
+
+/// Compares positions without the move counters.
+fn epd(fen: &str) -> Result<String, EngineManagerError> {
+    let position = parse_position(fen)?;
+    Ok(Epd::from_position(&position, EnPassantMode::Legal).to_string())
+}
+
+fn position_features(fen: &str, moves: &[String]) -> Result<PositionFeatures, EngineManagerError> {
+    let mut position = parse_position(fen)?;
+    let mut earlier = Vec::new();
+    // shakmaty also reads castling as king-takes-rook (e1h1), which the
+    // engine would reject, so the engine gets each move in standard form.
+    let mut engine_moves = Vec::with_capacity(moves.len());
+
+    for (index, uci) in moves.iter().enumerate() {
+        let parsed: UciMove = uci.parse().map_err(|_| {
+            EngineManagerError::InvalidRequest(format!(
+                "moves[{index}] ({uci}) is not a valid UCI move"
+            ))
+        })?;
+        let legal = parsed.to_move(&position).map_err(|_| {
+            EngineManagerError::InvalidRequest(format!(
+                "moves[{index}] ({uci}) is not legal in {}",
+                Fen::from_position(&position, EnPassantMode::Legal)
+            ))
+        })?;
+        engine_moves.push(legal.to_uci(CastlingMode::Standard).to_string());
+        if legal.is_zeroing() {
+            earlier.clear();
+        } else {
+            earlier.push(position.zobrist_hash::<Zobrist64>(EnPassantMode::Legal).0);
+        }
+        position.play_unchecked(legal);
+    }
 
+    let report = position_report::build(&position);
     Ok(PositionFeatures {
-        legal_move_count: position.legal_moves().len(),
-        in_check: position.is_check(),
+        legal_move_count: report.legal_move_count,
+        in_check: report.in_check,
+        report,
+        game_over: game_over::detect(&position),
+        zobrist: position.zobrist_hash::<Zobrist64>(EnPassantMode::Legal).0,
+        history: cache::history_hash(&earlier),
+        position: UciPosition {
+            fen: fen.to_string(),
+            moves: engine_moves,
+        },
+        fen: Fen::from_position(&position, EnPassantMode::Legal).to_string(),
+        board: position,
+        opening: None,
     })
 }
@@ -1,6 +1,22 @@
+pub mod actor;
 pub mod analysis;
+pub mod batch;
+pub mod book;
+pub mod cache;
 pub mod difficulty;
+pub mod disk_cache;
+pub mod game_over;
 pub mod installer;
+pub mod jobs;
 pub mod manager;
+pub mod move_eval;
+pub mod notation;
+pub mod openings;
+pub mod pool;
+pub mod position_report;
+pub mod review;
 pub mod stockfish;
+pub mod tablebase;
+pub mod threat;
 pub mod uci;
+pub mod wdl;
@@ -0,0 +1,50 @@
+use shakmaty::{san::SanPlus, uci::UciMove, Chess, Move};
+
+use crate::{
+    api::types::AnalysisLine,
+    engine::{
+        manager::EngineManagerError,
+        review::{self, MoveClassification},
+    },
+};
+
+/// Reads `text` as a UCI move such as `g1f3`, or failing that as SAN such
+/// as `Nf3`, and checks that it is legal in `position`.
+pub fn parse_move(position: &Chess, text: &str) -> Result<Move, EngineManagerError> {
+    let text = text.trim();
+    let legal = match text.parse::<UciMove>() {
+        Ok(uci) => uci.to_move(position).ok(),
+        Err(_) => text
+            .parse::<SanPlus>()
+            .ok()
+            .and_then(|san| san.san.to_move(position).ok()),
+    };
+    legal.ok_or_else(|| {
+        EngineManagerError::InvalidRequest(format!("move {text} is not legal in this position"))
+    })
+}
+
+/// Centipawn loss and classification of a move, from the best line of the
+/// full search and the line of the search restricted to the move. Both
+/// scores are for the side to move, and mates count as the review's cap.
+pub fn judge(
+    best: Option<&AnalysisLine>,
+    played: Option<&AnalysisLine>,
+    is_best: bool,
+) -> (u32, MoveClassification) {
+    if is_best {
+        return (0, MoveClassification::Best);
+    }
+    let best_cp = best.map_or(0, review::capped_cp);
+    let played_cp = played.map_or(0, review::capped_cp);
+    let centipawn_loss = (best_cp - played_cp).max(0) as u32;
+
+    let mates =
+        |line: Option<&AnalysisLine>| line.and_then(|line| line.mate).is_some_and(|mate| mate > 0);
+    let classification = if mates(best) && !mates(played) {
+        MoveClassification::MissedMate
+    } else {
+        review::classify(centipawn_loss)
+    };
+    (centipawn_loss, classification)
+}
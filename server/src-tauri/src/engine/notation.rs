@@ -0,0 +1,219 @@
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+use shakmaty::{
+    fen::Fen, san::SanPlus, uci::UciMove, CastlingMode, CastlingSide, Chess, Color, EnPassantMode,
+    Move, Position, Role, Square,
+};
+
+use crate::{api::types::AnalysisLine, engine::uci::UciPosition};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PieceColor {
+    White,
+    Black,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PieceRole {
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CastleSide {
+    KingSide,
+    QueenSide,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SquareDetail {
+    /// Algebraic name, such as `e4`.
+    pub square: String,
+    /// 0 for the a-file up to 7 for the h-file.
+    pub file: u8,
+    /// 0 for the first rank up to 7 for the eighth.
+    pub rank: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CastlingDetail {
+    pub side: CastleSide,
+    pub rook_from: SquareDetail,
+    pub rook_to: SquareDetail,
+}
+
+/// A legal move spelled out, so clients need not take UCI strings apart.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveDetail {
+    pub uci: String,
+    pub san: String,
+    pub color: PieceColor,
+    pub piece: PieceRole,
+    pub from: SquareDetail,
+    /// Where the piece lands; for castling, the king's destination.
+    pub to: SquareDetail,
+    pub captured: Option<PieceRole>,
+    /// Square of the captured piece, which differs from `to` for en passant.
+    pub capture_square: Option<SquareDetail>,
+    pub promotion: Option<PieceRole>,
+    pub en_passant: bool,
+    pub castling: Option<CastlingDetail>,
+}
+
+/// The position a UCI `position` command describes, or `None` when its FEN
+/// or one of its moves is invalid.
+pub fn replay(position: &UciPosition) -> Option<Chess> {
+    let fen: Fen = position.fen.parse().ok()?;
+    let mut board: Chess = fen.into_position(CastlingMode::Standard).ok()?;
+    for uci in &position.moves {
+        let legal = legal_move(&board, uci)?;
+        board.play_unchecked(legal);
+    }
+    Some(board)
+}
+
+/// Fills in the SAN fields of `line` by replaying its pv from `position`.
+///
+/// Replay stops at the first move that is not legal, so `pv_san` only holds
+/// the playable part of the line and `pv_illegal_index` points at the rest.
+pub fn annotate_line(position: &Chess, line: &mut AnalysisLine) {
+    let mut board = position.clone();
+    let mut pv_san = Vec::with_capacity(line.pv.len());
+    line.pv_illegal_index = None;
+    line.move_detail = None;
+
+    for (index, uci) in line.pv.iter().enumerate() {
+        let Some(legal) = legal_move(&board, uci) else {
+            line.pv_illegal_index = Some(index);
+            break;
+        };
+        if index == 0 {
+            line.move_detail = Some(move_detail(&board, legal));
+        }
+        pv_san.push(SanPlus::from_move_and_play_unchecked(&mut board, legal).to_string());
+    }
+
+    line.move_san = pv_san.first().cloned();
+    line.pv_text = (!pv_san.is_empty()).then(|| numbered_pv(position, &pv_san));
+    line.pv_san = pv_san;
+}
+
+/// SAN moves with move numbers, such as `12... Nxe4 13. Bxe4 d5`.
+pub fn numbered_pv(position: &Chess, pv_san: &[String]) -> String {
+    let mut text = String::new();
+    let mut fullmove = position.fullmoves().get();
+    let mut turn = position.turn();
+
+    for (index, san) in pv_san.iter().enumerate() {
+        if index > 0 {
+            text.push(' ');
+        }
+        match turn {
+            Color::White => {
+                let _ = write!(text, "{fullmove}. ");
+            }
+            Color::Black if index == 0 => {
+                let _ = write!(text, "{fullmove}... ");
+            }
+            Color::Black => {}
+        }
+        text.push_str(san);
+        if turn == Color::Black {
+            fullmove += 1;
+        }
+        turn = !turn;
+    }
+    text
+}
+
+/// The position after `uci`, or `None` when the move is not legal.
+pub fn play(position: &Chess, uci: &str) -> Option<Chess> {
+    let legal = legal_move(position, uci)?;
+    let mut board = position.clone();
+    board.play_unchecked(legal);
+    Some(board)
+}
+
+/// FEN of the position after `uci`, or `None` when the move is not legal.
+pub fn fen_after(position: &Chess, uci: &str) -> Option<String> {
+    play(position, uci).map(|board| Fen::from_position(&board, EnPassantMode::Legal).to_string())
+}
+
+/// Describes `uci` as played in `position`, or `None` when it is not legal there.
+pub fn describe_move(position: &Chess, uci: &str) -> Option<MoveDetail> {
+    legal_move(position, uci).map(|legal| move_detail(position, legal))
+}
+
+pub(crate) fn move_detail(position: &Chess, legal: Move) -> MoveDetail {
+    let color = position.turn();
+    let from = legal
+        .from()
+        .expect("moves in standard chess start from a square");
+    let (to, castling) = match legal.castling_side() {
+        Some(side) => (
+            side.king_to(color),
+            Some(CastlingDetail {
+                side: match side {
+                    CastlingSide::KingSide => CastleSide::KingSide,
+                    CastlingSide::QueenSide => CastleSide::QueenSide,
+                },
+                rook_from: square_detail(legal.to()),
+                rook_to: square_detail(side.rook_to(color)),
+            }),
+        ),
+        None => (legal.to(), None),
+    };
+    let capture_square = match legal {
+        Move::EnPassant { from, to } => Some(Square::from_coords(to.file(), from.rank())),
+        _ if legal.is_capture() => Some(to),
+        _ => None,
+    };
+
+    MoveDetail {
+        uci: legal.to_uci(CastlingMode::Standard).to_string(),
+        san: SanPlus::from_move(position.clone(), legal).to_string(),
+        color: match color {
+            Color::White => PieceColor::White,
+            Color::Black => PieceColor::Black,
+        },
+        piece: piece_role(legal.role()),
+        from: square_detail(from),
+        to: square_detail(to),
+        captured: legal.capture().map(piece_role),
+        capture_square: capture_square.map(square_detail),
+        promotion: legal.promotion().map(piece_role),
+        en_passant: legal.is_en_passant(),
+        castling,
+    }
+}
+
+pub(crate) fn square_detail(square: Square) -> SquareDetail {
+    SquareDetail {
+        square: square.to_string(),
+        file: u8::from(square.file()),
+        rank: u8::from(square.rank()),
+    }
+}
+
+pub(crate) fn piece_role(role: Role) -> PieceRole {
+    match role {
+        Role::Pawn => PieceRole::Pawn,
+        Role::Knight => PieceRole::Knight,
+        Role::Bishop => PieceRole::Bishop,
+        Role::Rook => PieceRole::Rook,
+        Role::Queen => PieceRole::Queen,
+        Role::King => PieceRole::King,
+    }
+}
+
+fn legal_move(position: &Chess, uci: &str) -> Option<Move> {
+    uci.parse::<UciMove>().ok()?.to_move(position).ok()
+}
@@ -0,0 +1,134 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use shakmaty::{fen::Epd, san::San, Chess, EnPassantMode, Position};
+
+use crate::engine::{notation, uci::UciPosition};
+
+/// Openings shipped with the app, in the lichess chess-openings TSV format.
+const BUNDLED: &str = include_str!("openings.tsv");
+/// Directory under the data dir whose `.tsv` files extend the bundled table.
+pub const OPENINGS_DIR: &str = "openings";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Opening {
+    pub eco: String,
+    pub name: String,
+    /// Half-moves in the opening's defining line.
+    pub ply: usize,
+}
+
+/// Opening names by position, so transpositions find the same entry.
+#[derive(Debug, Default)]
+pub struct OpeningTable {
+    by_epd: HashMap<String, Opening>,
+}
+
+impl OpeningTable {
+    pub fn bundled() -> Self {
+        let mut table = Self::default();
+        table.add_tsv(BUNDLED);
+        table
+    }
+
+    /// The bundled table plus every `.tsv` file in `dir`, whose rows win
+    /// over bundled ones for the same position. A missing `dir` is fine.
+    pub fn load(dir: &Path) -> Self {
+        let mut table = Self::bundled();
+        let mut files: Vec<_> = match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "tsv"))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        files.sort();
+
+        for path in files {
+            match fs::read_to_string(&path) {
+                Ok(text) => {
+                    let added = table.add_tsv(&text);
+                    tracing::info!(path = %path.display(), added, "loaded opening names");
+                }
+                Err(err) => {
+                    tracing::warn!(path = %path.display(), %err, "could not read opening names")
+                }
+            }
+        }
+        table
+    }
+
+    /// Adds `eco<TAB>name<TAB>pgn` rows and returns how many were added.
+    /// The header row and rows whose moves do not replay are skipped.
+    pub fn add_tsv(&mut self, text: &str) -> usize {
+        let mut added = 0;
+        for row in text.lines() {
+            let mut columns = row.split('\t');
+            let (Some(eco), Some(name), Some(pgn)) =
+                (columns.next(), columns.next(), columns.next())
+            else {
+                continue;
+            };
+            let Some((position, ply)) = replay_san(pgn) else {
+                continue;
+            };
+            self.by_epd.insert(
+                epd(&position),
+                Opening {
+                    eco: eco.to_string(),
+                    name: name.to_string(),
+                    ply,
+                },
+            );
+            added += 1;
+        }
+        added
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_epd.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_epd.is_empty()
+    }
+
+    pub fn get(&self, position: &Chess) -> Option<&Opening> {
+        self.by_epd.get(&epd(position))
+    }
+
+    /// The last named opening along a game: the position itself when it has
+    /// a name, otherwise the latest earlier position that has one.
+    pub fn classify(&self, position: &UciPosition) -> Option<Opening> {
+        let mut board = notation::replay(&UciPosition {
+            fen: position.fen.clone(),
+            moves: Vec::new(),
+        })?;
+        let mut found = self.get(&board);
+        for uci in &position.moves {
+            board = notation::play(&board, uci)?;
+            found = self.get(&board).or(found);
+        }
+        found.cloned()
+    }
+}
+
+/// Plays a SAN line such as `1. e4 e5 2. Nf3` from the start position.
+fn replay_san(pgn: &str) -> Option<(Chess, usize)> {
+    let mut position = Chess::default();
+    let mut ply = 0;
+    for token in pgn.split_whitespace() {
+        if token.ends_with('.') {
+            continue;
+        }
+        let san: San = token.trim_end_matches(['+', '#']).parse().ok()?;
+        let legal = san.to_move(&position).ok()?;
+        position.play_unchecked(legal);
+        ply += 1;
+    }
+    (ply > 0).then_some((position, ply))
+}
+
+fn epd(position: &Chess) -> String {
+    Epd::from_position(position, EnPassantMode::Legal).to_string()
+}
@@ -0,0 +1,164 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
+
+use tokio::sync::Notify;
+
+use crate::engine::{actor::EngineActor, manager::EngineManagerError, manager::EngineStatus};
+
+/// One engine process slot: its actor and whether a request holds it.
+pub struct Worker {
+    /// Stable across resizes, unlike the worker's position in the pool.
+    pub id: u64,
+    pub actor: EngineActor,
+    busy: AtomicBool,
+}
+
+impl Worker {
+    fn spawn(id: u64) -> Self {
+        Self {
+            id,
+            actor: EngineActor::spawn(),
+            busy: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.busy.load(Ordering::SeqCst)
+    }
+}
+
+/// Fixed set of engine workers that analysis requests are scheduled onto.
+///
+/// The first worker is the primary one: it also serves infinite analysis and
+/// provides the option catalogue.
+pub struct EnginePool {
+    workers: RwLock<Vec<Arc<Worker>>>,
+    released: Arc<Notify>,
+    next_id: AtomicU64,
+}
+
+impl EnginePool {
+    pub fn new() -> Self {
+        Self {
+            workers: RwLock::new(vec![Arc::new(Worker::spawn(0))]),
+            released: Arc::new(Notify::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    pub fn primary(&self) -> Arc<Worker> {
+        self.workers()[0].clone()
+    }
+
+    pub fn workers(&self) -> Vec<Arc<Worker>> {
+        self.workers
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    pub fn find(&self, id: u64) -> Option<Arc<Worker>> {
+        self.workers().into_iter().find(|worker| worker.id == id)
+    }
+
+    /// Grows or shrinks the pool to `size` workers and returns the new ones.
+    ///
+    /// Removed workers finish their current request, then their threads exit.
+    pub fn resize(&self, size: usize) -> Vec<Arc<Worker>> {
+        let size = size.max(1);
+        let mut workers = self.workers.write().unwrap_or_else(|err| err.into_inner());
+        workers.truncate(size);
+
+        let mut added = Vec::new();
+        while workers.len() < size {
+            let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+            let worker = Arc::new(Worker::spawn(id));
+            workers.push(worker.clone());
+            added.push(worker);
+        }
+        added
+    }
+
+    /// Waits up to `timeout` for an idle worker.
+    pub async fn lease(&self, timeout: Duration) -> Result<WorkerLease, EngineManagerError> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+
+            if let Some(lease) = self.try_lease() {
+                return Ok(lease);
+            }
+
+            if tokio::time::timeout_at(deadline, released).await.is_err() {
+                return Err(EngineManagerError::EngineBusy(
+                    "Every engine worker is busy. Try again shortly.".to_string(),
+                ));
+            }
+        }
+    }
+
+    /// Leases an idle worker with a running engine, falling back to an idle
+    /// worker without one so the caller gets the proper not-configured or
+    /// crashed error.
+    fn try_lease(&self) -> Option<WorkerLease> {
+        let workers = self.workers();
+        let idle = |worker: &&Arc<Worker>| {
+            !worker.is_busy()
+                && !matches!(
+                    worker.actor.snapshot().status,
+                    EngineStatus::InfiniteAnalysis
+                )
+        };
+        let candidates = workers
+            .iter()
+            .filter(idle)
+            .filter(|worker| worker.actor.snapshot().has_process)
+            .chain(workers.iter().filter(idle));
+
+        for worker in candidates {
+            if worker
+                .busy
+                .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                return Some(WorkerLease {
+                    worker: worker.clone(),
+                    released: self.released.clone(),
+                });
+            }
+        }
+        None
+    }
+}
+
+impl Default for EnginePool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Exclusive use of one worker; frees it again when dropped.
+pub struct WorkerLease {
+    worker: Arc<Worker>,
+    released: Arc<Notify>,
+}
+
+impl WorkerLease {
+    pub fn worker(&self) -> &Arc<Worker> {
+        &self.worker
+    }
+}
+
+impl Drop for WorkerLease {
+    fn drop(&mut self) {
+        self.worker.busy.store(false, Ordering::SeqCst);
+        self.released.notify_waiters();
+    }
+}
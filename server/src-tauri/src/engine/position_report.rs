@@ -0,0 +1,255 @@
+use serde::Serialize;
+use shakmaty::{
+    attacks, Bitboard, CastlingSide, Chess, Color, EnPassantMode, Move, Position, Role, Square,
+};
+
+use crate::engine::notation::{self, MoveDetail, PieceColor, PieceRole, SquareDetail};
+
+/// Phase weight of each minor piece, rook and queen; 24 with all of them on the board.
+const PHASE_WEIGHTS: [(Role, u8); 4] = [
+    (Role::Knight, 1),
+    (Role::Bishop, 1),
+    (Role::Rook, 2),
+    (Role::Queen, 4),
+];
+const MAX_PHASE_SCORE: u8 = 24;
+/// At most this much phase weight left counts as an endgame, e.g. two rooks each.
+const ENDGAME_PHASE_SCORE: u8 = 8;
+/// Up to this move, with at most one minor piece traded, the game is still in the opening.
+const OPENING_LAST_MOVE: u32 = 10;
+
+/// What can be read off a position without searching it.
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionReport {
+    pub side_to_move: PieceColor,
+    pub in_check: bool,
+    pub legal_move_count: usize,
+    pub material: Material,
+    pub phase: GamePhase,
+    /// Minor pieces count 1, rooks 2 and queens 4, from 24 at the start down to 0.
+    pub phase_score: u8,
+    /// Pieces other than the king that are attacked and not defended.
+    pub hanging: BySide<Vec<PlacedPiece>>,
+    /// Pieces that cannot leave the line between their king and an enemy slider.
+    pub pinned: BySide<Vec<PinnedPiece>>,
+    /// Legal moves for the side to move that give check.
+    pub checks: Vec<MoveDetail>,
+    /// Legal captures for the side to move, en passant included.
+    pub captures: Vec<MoveDetail>,
+    pub castling: BySide<CastlingRights>,
+    /// The en passant target square, only when a capture there is legal.
+    pub en_passant: Option<SquareDetail>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GamePhase {
+    Opening,
+    Middlegame,
+    Endgame,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BySide<T> {
+    pub white: T,
+    pub black: T,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Material {
+    pub white: MaterialCount,
+    pub black: MaterialCount,
+    /// White's points minus Black's.
+    pub balance: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MaterialCount {
+    pub pawns: u8,
+    pub knights: u8,
+    pub bishops: u8,
+    pub rooks: u8,
+    pub queens: u8,
+    /// Pawns 1, knights and bishops 3, rooks 5 and queens 9.
+    pub points: i32,
+    pub bishop_pair: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlacedPiece {
+    pub piece: PieceRole,
+    pub square: SquareDetail,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PinnedPiece {
+    pub piece: PieceRole,
+    pub square: SquareDetail,
+    /// The enemy rook, bishop or queen doing the pinning.
+    pub pinned_by: SquareDetail,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CastlingRights {
+    pub king_side: bool,
+    pub queen_side: bool,
+}
+
+pub fn build(position: &Chess) -> PositionReport {
+    let legal_moves = position.legal_moves();
+    let phase_score = phase_score(position);
+
+    PositionReport {
+        side_to_move: piece_color(position.turn()),
+        in_check: position.is_check(),
+        legal_move_count: legal_moves.len(),
+        material: material(position),
+        phase: if phase_score <= ENDGAME_PHASE_SCORE {
+            GamePhase::Endgame
+        } else if position.fullmoves().get() <= OPENING_LAST_MOVE
+            && phase_score >= MAX_PHASE_SCORE - 1
+        {
+            GamePhase::Opening
+        } else {
+            GamePhase::Middlegame
+        },
+        phase_score,
+        hanging: BySide {
+            white: hanging(position, Color::White),
+            black: hanging(position, Color::Black),
+        },
+        pinned: BySide {
+            white: pinned(position, Color::White),
+            black: pinned(position, Color::Black),
+        },
+        checks: legal_moves
+            .iter()
+            .filter(|legal| gives_check(position, **legal))
+            .map(|legal| notation::move_detail(position, *legal))
+            .collect(),
+        captures: legal_moves
+            .iter()
+            .filter(|legal| legal.is_capture())
+            .map(|legal| notation::move_detail(position, *legal))
+            .collect(),
+        castling: BySide {
+            white: castling_rights(position, Color::White),
+            black: castling_rights(position, Color::Black),
+        },
+        en_passant: position
+            .ep_square(EnPassantMode::Legal)
+            .map(notation::square_detail),
+    }
+}
+
+fn material(position: &Chess) -> Material {
+    let count = |color: Color| {
+        let side = position.board().material_side(color);
+        MaterialCount {
+            pawns: side.pawn,
+            knights: side.knight,
+            bishops: side.bishop,
+            rooks: side.rook,
+            queens: side.queen,
+            points: i32::from(side.pawn)
+                + 3 * i32::from(side.knight)
+                + 3 * i32::from(side.bishop)
+                + 5 * i32::from(side.rook)
+                + 9 * i32::from(side.queen),
+            bishop_pair: has_bishop_pair(position, color),
+        }
+    };
+    let (white, black) = (count(Color::White), count(Color::Black));
+
+    Material {
+        balance: white.points - black.points,
+        white,
+        black,
+    }
+}
+
+/// Bishops on both square colours; two bishops on the same colour are no pair.
+fn has_bishop_pair(position: &Chess, color: Color) -> bool {
+    let bishops = position.board().by_color(color) & position.board().by_role(Role::Bishop);
+    (bishops & Bitboard::LIGHT_SQUARES).any() && (bishops & Bitboard::DARK_SQUARES).any()
+}
+
+fn phase_score(position: &Chess) -> u8 {
+    let board = position.board();
+    let score: usize = PHASE_WEIGHTS
+        .iter()
+        .map(|(role, weight)| board.by_role(*role).count() * usize::from(*weight))
+        .sum();
+    score.min(usize::from(MAX_PHASE_SCORE)) as u8
+}
+
+fn hanging(position: &Chess, color: Color) -> Vec<PlacedPiece> {
+    let board = position.board();
+    let occupied = board.occupied();
+    (board.by_color(color) & !board.by_role(Role::King))
+        .into_iter()
+        .filter(|square| {
+            board.attacks_to(*square, color.other(), occupied).any()
+                && board.attacks_to(*square, color, occupied).is_empty()
+        })
+        .map(|square| placed_piece(position, square))
+        .collect()
+}
+
+fn pinned(position: &Chess, color: Color) -> Vec<PinnedPiece> {
+    let board = position.board();
+    let Some(king) = board.king_of(color) else {
+        return Vec::new();
+    };
+    let enemy = board.by_color(color.other());
+    let snipers = (attacks::rook_attacks(king, Bitboard::EMPTY) & board.rooks_and_queens()
+        | attacks::bishop_attacks(king, Bitboard::EMPTY) & board.bishops_and_queens())
+        & enemy;
+
+    snipers
+        .into_iter()
+        .filter_map(|sniper| {
+            let blocker = (attacks::between(king, sniper) & board.occupied()).single_square()?;
+            board.by_color(color).contains(blocker).then(|| {
+                let PlacedPiece { piece, square } = placed_piece(position, blocker);
+                PinnedPiece {
+                    piece,
+                    square,
+                    pinned_by: notation::square_detail(sniper),
+                }
+            })
+        })
+        .collect()
+}
+
+fn gives_check(position: &Chess, legal: Move) -> bool {
+    let mut after = position.clone();
+    after.play_unchecked(legal);
+    after.is_check()
+}
+
+fn castling_rights(position: &Chess, color: Color) -> CastlingRights {
+    let castles = position.castles();
+    CastlingRights {
+        king_side: castles.has(color, CastlingSide::KingSide),
+        queen_side: castles.has(color, CastlingSide::QueenSide),
+    }
+}
+
+fn placed_piece(position: &Chess, square: Square) -> PlacedPiece {
+    let role = position
+        .board()
+        .role_at(square)
+        .expect("squares come from the occupied set");
+    PlacedPiece {
+        piece: notation::piece_role(role),
+        square: notation::square_detail(square),
+    }
+}
+
+fn piece_color(color: Color) -> PieceColor {
+    match color {
+        Color::White => PieceColor::White,
+        Color::Black => PieceColor::Black,
+    }
+}
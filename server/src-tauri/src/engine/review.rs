@@ -0,0 +1,448 @@
+use serde::Serialize;
+use shakmaty::{
+    fen::Fen, san::SanPlus, uci::UciMove, CastlingMode, Chess, Color, EnPassantMode, Position,
+};
+
+use crate::{
+    api::types::{AnalysisLine, AnalyzeResponse},
+    engine::{
+        game_over::{GameOver, GameOverReason},
+        manager::{parse_position, EngineManagerError, STARTING_FEN},
+        notation::PieceColor,
+    },
+};
+
+/// Longest game a review accepts, in half-moves.
+pub const MAX_REVIEW_PLIES: usize = 600;
+/// Scores are capped here, and forced mates count as this much.
+const EVAL_CAP_CP: i32 = 1_000;
+const INACCURACY_CP: u32 = 50;
+const MISTAKE_CP: u32 = 100;
+const BLUNDER_CP: u32 = 300;
+
+/// A validated game: its start position, the moves played, and every
+/// position along the way (one more than there are moves).
+#[derive(Debug, Clone)]
+pub struct ReviewGame {
+    pub start_fen: String,
+    pub moves: Vec<String>,
+    pub sans: Vec<String>,
+    pub positions: Vec<Chess>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveClassification {
+    Best,
+    Good,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+    MissedMate,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GameReview {
+    pub review_id: String,
+    pub start_fen: String,
+    pub depth: u32,
+    pub engine_name: Option<String>,
+    pub moves: Vec<ReviewedMove>,
+    pub white: SideReview,
+    pub black: SideReview,
+    /// White's evaluation before the first move and after every move.
+    pub eval_curve: Vec<EvalPoint>,
+    /// How the game ended, when the final position is already decided.
+    pub game_over: Option<GameOver>,
+    pub time_taken_ms: u128,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReviewedMove {
+    /// 1 for White's first move.
+    pub ply: usize,
+    pub move_number: u32,
+    pub color: PieceColor,
+    pub move_uci: String,
+    pub move_san: String,
+    pub fen_before: String,
+    pub best_move: Option<String>,
+    pub best_move_san: Option<String>,
+    /// Evaluations from the mover's point of view, capped at ±1000.
+    pub eval_before_cp: i32,
+    pub eval_after_cp: i32,
+    pub centipawn_loss: u32,
+    pub accuracy: f64,
+    pub classification: MoveClassification,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SideReview {
+    pub moves: usize,
+    /// `None` when the side made no moves.
+    pub accuracy: Option<f64>,
+    pub average_centipawn_loss: Option<f64>,
+    pub inaccuracies: usize,
+    pub mistakes: usize,
+    pub blunders: usize,
+    pub missed_mates: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalPoint {
+    /// 0 for the start position.
+    pub ply: usize,
+    /// Capped at ±1000, with forced mates at the cap.
+    pub white_cp: i32,
+    pub white_mate: Option<i32>,
+}
+
+/// Reads the first game of a PGN. A `FEN` tag sets the start position;
+/// comments, variations, NAGs and move-quality marks are skipped.
+pub fn from_pgn(pgn: &str) -> Result<ReviewGame, EngineManagerError> {
+    let mut start_fen = STARTING_FEN.to_string();
+    let mut movetext = String::new();
+    for line in pgn.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            if let Some(fen) = tag_value(line, "FEN") {
+                start_fen = fen;
+            }
+        } else if !line.starts_with('%') {
+            movetext.push_str(line);
+            movetext.push('\n');
+        }
+    }
+
+    let mut position = parse_position(&start_fen)?;
+    let mut moves = Vec::new();
+    for token in movetext_tokens(&movetext) {
+        if matches!(token.as_str(), "1-0" | "0-1" | "1/2-1/2" | "*") {
+            break;
+        }
+        let Some(san) = san_token(&token) else {
+            continue;
+        };
+
+        let label = move_label(&position, &token);
+        let legal = san
+            .parse::<SanPlus>()
+            .map_err(|_| invalid(format!("pgn move {label} is not valid SAN")))?
+            .san
+            .to_move(&position)
+            .map_err(|_| invalid(format!("pgn move {label} is not legal")))?;
+        moves.push(legal.to_uci(CastlingMode::Standard).to_string());
+        position.play_unchecked(legal);
+    }
+
+    from_moves(&start_fen, &moves)
+}
+
+/// Replays UCI `moves` from `start_fen`.
+pub fn from_moves(start_fen: &str, moves: &[String]) -> Result<ReviewGame, EngineManagerError> {
+    if moves.is_empty() {
+        return Err(invalid("the game has no moves to review".to_string()));
+    }
+    if moves.len() > MAX_REVIEW_PLIES {
+        return Err(invalid(format!(
+            "games of at most {MAX_REVIEW_PLIES} half-moves can be reviewed"
+        )));
+    }
+
+    let mut position = parse_position(start_fen)?;
+    let mut positions = vec![position.clone()];
+    let mut sans = Vec::with_capacity(moves.len());
+    // Castling sent as king-takes-rook (e1h1) is passed on as e1g1.
+    let mut engine_moves = Vec::with_capacity(moves.len());
+    for (index, uci) in moves.iter().enumerate() {
+        let parsed: UciMove = uci
+            .parse()
+            .map_err(|_| invalid(format!("moves[{index}] ({uci}) is not a valid UCI move")))?;
+        let legal = parsed.to_move(&position).map_err(|_| {
+            invalid(format!(
+                "moves[{index}] ({uci}) is not legal in {}",
+                Fen::from_position(&position, EnPassantMode::Legal)
+            ))
+        })?;
+        engine_moves.push(legal.to_uci(CastlingMode::Standard).to_string());
+        sans.push(SanPlus::from_move_and_play_unchecked(&mut position, legal).to_string());
+        positions.push(position.clone());
+    }
+
+    Ok(ReviewGame {
+        start_fen: start_fen.to_string(),
+        moves: engine_moves,
+        sans,
+        positions,
+    })
+}
+
+/// Scores every move of `game` from one analysis per position.
+///
+/// `evals[i]` is the analysis of `game.positions[i]`, so there is one more
+/// than there are moves.
+pub fn build(
+    review_id: String,
+    game: &ReviewGame,
+    evals: &[AnalyzeResponse],
+    depth: u32,
+    time_taken_ms: u128,
+) -> GameReview {
+    let mut moves = Vec::with_capacity(game.moves.len());
+    let mut white = SideReview::default();
+    let mut black = SideReview::default();
+
+    for (index, (uci, san)) in game.moves.iter().zip(&game.sans).enumerate() {
+        let position = &game.positions[index];
+        let (before, after) = (&evals[index], &evals[index + 1]);
+        let eval_before_cp = side_to_move_cp(before);
+        let eval_after_cp = -side_to_move_cp(after);
+        let played_best = before.best_move.as_deref() == Some(uci.as_str());
+        let centipawn_loss = if played_best {
+            0
+        } else {
+            (eval_before_cp - eval_after_cp).max(0) as u32
+        };
+        let classification = if played_best {
+            MoveClassification::Best
+        } else if had_forced_mate(before) && !keeps_forced_mate(after) {
+            MoveClassification::MissedMate
+        } else {
+            classify(centipawn_loss)
+        };
+        let accuracy = if played_best {
+            100.0
+        } else {
+            move_accuracy(eval_before_cp, eval_after_cp)
+        };
+
+        let color = position.turn();
+        let side = match color {
+            Color::White => &mut white,
+            Color::Black => &mut black,
+        };
+        side.moves += 1;
+        side.accuracy = Some(side.accuracy.unwrap_or(0.0) + accuracy);
+        side.average_centipawn_loss =
+            Some(side.average_centipawn_loss.unwrap_or(0.0) + f64::from(centipawn_loss));
+        match classification {
+            MoveClassification::Inaccuracy => side.inaccuracies += 1,
+            MoveClassification::Mistake => side.mistakes += 1,
+            MoveClassification::Blunder => side.blunders += 1,
+            MoveClassification::MissedMate => side.missed_mates += 1,
+            MoveClassification::Best | MoveClassification::Good => {}
+        }
+
+        moves.push(ReviewedMove {
+            ply: index + 1,
+            move_number: position.fullmoves().get(),
+            color: match color {
+                Color::White => PieceColor::White,
+                Color::Black => PieceColor::Black,
+            },
+            move_uci: uci.clone(),
+            move_san: san.clone(),
+            fen_before: Fen::from_position(position, EnPassantMode::Legal).to_string(),
+            best_move: before.best_move.clone(),
+            best_move_san: best_move_san(position, before.best_move.as_deref()),
+            eval_before_cp,
+            eval_after_cp,
+            centipawn_loss,
+            accuracy: round1(accuracy),
+            classification,
+        });
+    }
+
+    for side in [&mut white, &mut black] {
+        let count = side.moves as f64;
+        side.accuracy = side.accuracy.map(|total| round1(total / count));
+        side.average_centipawn_loss = side
+            .average_centipawn_loss
+            .map(|total| round1(total / count));
+    }
+
+    let eval_curve = evals
+        .iter()
+        .zip(&game.positions)
+        .enumerate()
+        .map(|(ply, (eval, position))| {
+            let sign = if position.turn() == Color::White {
+                1
+            } else {
+                -1
+            };
+            EvalPoint {
+                ply,
+                white_cp: sign * side_to_move_cp(eval),
+                white_mate: top_line(eval).and_then(|line| line.white_mate),
+            }
+        })
+        .collect();
+
+    GameReview {
+        review_id,
+        start_fen: game.start_fen.clone(),
+        depth,
+        engine_name: evals.iter().rev().find_map(|eval| eval.engine.name.clone()),
+        moves,
+        white,
+        black,
+        eval_curve,
+        game_over: evals.last().and_then(|eval| eval.game_over),
+        time_taken_ms,
+    }
+}
+
+/// Centipawn-loss bands for moves that are not the engine's choice.
+pub fn classify(centipawn_loss: u32) -> MoveClassification {
+    match centipawn_loss {
+        loss if loss >= BLUNDER_CP => MoveClassification::Blunder,
+        loss if loss >= MISTAKE_CP => MoveClassification::Mistake,
+        loss if loss >= INACCURACY_CP => MoveClassification::Inaccuracy,
+        _ => MoveClassification::Good,
+    }
+}
+
+/// Chance of winning, in percent, for a side with an advantage of `cp`.
+pub fn win_percent(cp: i32) -> f64 {
+    50.0 + 50.0 * (2.0 / (1.0 + (-0.003_682_08 * f64::from(cp)).exp()) - 1.0)
+}
+
+/// Accuracy of a single move, from how much it lowered the mover's winning chances.
+pub fn move_accuracy(eval_before_cp: i32, eval_after_cp: i32) -> f64 {
+    let drop = win_percent(eval_before_cp) - win_percent(eval_after_cp);
+    (103.166_8 * (-0.043_54 * drop).exp() - 3.166_9).clamp(0.0, 100.0)
+}
+
+/// The side to move's evaluation, capped at ±`EVAL_CAP_CP`.
+fn side_to_move_cp(eval: &AnalyzeResponse) -> i32 {
+    if let Some(game_over) = eval.game_over {
+        return match game_over.reason {
+            GameOverReason::Checkmate => -EVAL_CAP_CP,
+            _ => 0,
+        };
+    }
+    top_line(eval).map_or(0, capped_cp)
+}
+
+/// A line's score capped at ±`EVAL_CAP_CP`, with forced mates at the cap.
+pub fn capped_cp(line: &AnalysisLine) -> i32 {
+    match line {
+        AnalysisLine {
+            mate: Some(mate), ..
+        } if *mate > 0 => EVAL_CAP_CP,
+        AnalysisLine { mate: Some(_), .. } => -EVAL_CAP_CP,
+        AnalysisLine {
+            score_cp: Some(cp), ..
+        } => (*cp).clamp(-EVAL_CAP_CP, EVAL_CAP_CP),
+        _ => 0,
+    }
+}
+
+fn had_forced_mate(before: &AnalyzeResponse) -> bool {
+    top_line(before)
+        .and_then(|line| line.mate)
+        .is_some_and(|mate| mate > 0)
+}
+
+/// Whether the mover still mates after the move: the opponent is mated, or
+/// has to face a forced mate.
+fn keeps_forced_mate(after: &AnalyzeResponse) -> bool {
+    after
+        .game_over
+        .is_some_and(|game_over| game_over.reason == GameOverReason::Checkmate)
+        || top_line(after)
+            .and_then(|line| line.mate)
+            .is_some_and(|mate| mate <= 0)
+}
+
+fn top_line(eval: &AnalyzeResponse) -> Option<&AnalysisLine> {
+    eval.lines.iter().min_by_key(|line| line.rank)
+}
+
+fn best_move_san(position: &Chess, best_move: Option<&str>) -> Option<String> {
+    let legal = best_move?.parse::<UciMove>().ok()?.to_move(position).ok()?;
+    Some(SanPlus::from_move(position.clone(), legal).to_string())
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+fn invalid(message: String) -> EngineManagerError {
+    EngineManagerError::InvalidRequest(message)
+}
+
+/// The value of tag `name` in a line such as `[FEN "..."]`.
+fn tag_value(line: &str, name: &str) -> Option<String> {
+    let inner = line.trim_start_matches('[').trim_end_matches(']').trim();
+    let (tag, value) = inner.split_once(char::is_whitespace)?;
+    (tag == name).then(|| value.trim().trim_matches('"').to_string())
+}
+
+/// Splits movetext into tokens, dropping comments, variations and NAGs.
+fn movetext_tokens(movetext: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut chars = movetext.chars();
+    let mut variation_depth = 0usize;
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' => {
+                for skipped in chars.by_ref() {
+                    if skipped == '}' {
+                        break;
+                    }
+                }
+            }
+            ';' => {
+                for skipped in chars.by_ref() {
+                    if skipped == '\n' {
+                        break;
+                    }
+                }
+            }
+            '(' => variation_depth += 1,
+            ')' => variation_depth = variation_depth.saturating_sub(1),
+            _ if variation_depth > 0 => continue,
+            _ if ch.is_whitespace() => {}
+            _ => {
+                current.push(ch);
+                continue;
+            }
+        }
+        if !current.is_empty() {
+            tokens.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens.retain(|token| !token.starts_with('$'));
+    tokens
+}
+
+/// The SAN part of a movetext token, without a move number or `!?` marks.
+fn san_token(token: &str) -> Option<String> {
+    let san = match token.rfind('.') {
+        Some(dot) => &token[dot + 1..],
+        None => token,
+    };
+    let san = san.trim_end_matches(['!', '?']);
+    if san.is_empty() {
+        return None;
+    }
+    Some(san.replace('0', "O"))
+}
+
+/// Names a token for error messages, such as `12... Nf3`.
+fn move_label(position: &Chess, token: &str) -> String {
+    let dots = if position.turn() == Color::White {
+        "."
+    } else {
+        "..."
+    };
+    let san = san_token(token).unwrap_or_default();
+    format!("{}{dots} {san}", position.fullmoves())
+}
@@ -1,6 +1,6 @@
 use std::{
     io::{self, BufRead, BufReader, Write},
-    path::Path,
+    path::{Path, PathBuf},
     process::{Child, ChildStdin, Command, Stdio},
     sync::mpsc::{self, Receiver},
     thread,
@@ -9,9 +9,15 @@ use std::{
 
 use thiserror::Error;
 
-use crate::engine::{
-    analysis::{AnalysisAccumulator, RawAnalysisResult},
-    uci::{info_to_analysis_line, parse_bestmove_line, parse_info_line},
+use crate::{
+    api::types::AnalysisLine,
+    engine::{
+        analysis::{AnalysisAccumulator, RawAnalysisResult},
+        uci::{
+            info_to_analysis_line, parse_bestmove_line, parse_info_line, parse_option_line,
+            UciOption, UciPosition,
+        },
+    },
 };
 
 #[cfg(target_os = "windows")]
@@ -20,6 +26,50 @@ use std::os::windows::process::CommandExt;
 #[cfg(target_os = "windows")]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
+/// How long a timed-out search may take to answer `stop`.
+const STOP_GRACE: Duration = Duration::from_secs(1);
+
+/// How far a `go` search runs, and which root moves it may play.
+#[derive(Debug, Clone, Copy)]
+pub struct SearchLimits<'a> {
+    pub depth: u32,
+    pub max_think_time_ms: u64,
+    pub disregard_think_time: bool,
+    /// Only these UCI moves are searched at the root; all of them when empty.
+    pub searchmoves: &'a [String],
+}
+
+impl SearchLimits<'_> {
+    pub fn go_command(&self) -> String {
+        let mut command = if self.disregard_think_time {
+            format!("go depth {}", self.depth)
+        } else {
+            format!(
+                "go depth {} movetime {}",
+                self.depth, self.max_think_time_ms
+            )
+        };
+        if !self.searchmoves.is_empty() {
+            command.push_str(" searchmoves ");
+            command.push_str(&self.searchmoves.join(" "));
+        }
+        command
+    }
+
+    /// How long to wait for `bestmove` before giving up on the engine.
+    fn timeout(&self) -> Duration {
+        if self.disregard_think_time {
+            Duration::from_secs(u64::from(self.depth).saturating_mul(3).clamp(10, 90))
+        } else {
+            Duration::from_millis(
+                self.max_think_time_ms
+                    .saturating_add(5_000)
+                    .clamp(5_000, 120_000),
+            )
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum StockfishError {
     #[error("io error: {0}")]
@@ -36,8 +86,30 @@ pub enum StockfishError {
     EngineExited,
     #[error("engine returned no bestmove")]
     MissingBestMove,
+    #[error("engine returned no line for {0}")]
+    MissingLine(String),
     #[error("engine communication error: {0}")]
     Communication(String),
+    #[error("invalid engine option: {0}")]
+    InvalidOption(String),
+}
+
+/// Executable, arguments and working directory used to start a UCI engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EngineLaunch {
+    pub path: PathBuf,
+    pub args: Vec<String>,
+    pub working_dir: Option<PathBuf>,
+}
+
+impl EngineLaunch {
+    pub fn from_path(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            args: Vec::new(),
+            working_dir: None,
+        }
+    }
 }
 
 pub struct StockfishProcess {
@@ -45,11 +117,22 @@ pub struct StockfishProcess {
     stdin: ChildStdin,
     lines: Receiver<String>,
     pub name: Option<String>,
+    /// Options advertised during the handshake.
+    options: Vec<UciOption>,
 }
 
 impl StockfishProcess {
     pub fn spawn(path: impl AsRef<Path>) -> Result<Self, StockfishError> {
-        let mut command = Command::new(path.as_ref());
+        Self::launch(&EngineLaunch::from_path(path.as_ref()))
+    }
+
+    /// Starts any UCI engine, not only Stockfish, and completes the handshake.
+    pub fn launch(launch: &EngineLaunch) -> Result<Self, StockfishError> {
+        let mut command = Command::new(&launch.path);
+        command.args(&launch.args);
+        if let Some(dir) = &launch.working_dir {
+            command.current_dir(dir);
+        }
 
         command
             .stdin(Stdio::piped())
@@ -94,6 +177,7 @@ impl StockfishProcess {
             stdin,
             lines: receiver,
             name: None,
+            options: Vec::new(),
         };
 
         process.handshake()?;
@@ -107,8 +191,28 @@ impl StockfishProcess {
         Ok(())
     }
 
+    pub fn options(&self) -> &[UciOption] {
+        &self.options
+    }
+
+    /// Looks up an advertised option. UCI option names are case-insensitive.
+    pub fn option(&self, name: &str) -> Option<&UciOption> {
+        self.options
+            .iter()
+            .find(|option| option.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn supports_option(&self, name: &str) -> bool {
+        self.option(name).is_some()
+    }
+
     pub fn new_game(&mut self) -> Result<(), StockfishError> {
         self.write_line("ucinewgame")?;
+        self.sync()
+    }
+
+    /// Waits for `readyok`, skipping whatever an earlier command left unread.
+    pub fn sync(&mut self) -> Result<(), StockfishError> {
         self.write_line("isready")?;
         self.wait_for_line(|line| line == "readyok", Duration::from_secs(5), "readyok")?;
         Ok(())
@@ -116,34 +220,16 @@ impl StockfishProcess {
 
     pub fn analyze(
         &mut self,
-        fen: &str,
-        depth: u32,
-        max_think_time_ms: u64,
-        disregard_think_time: bool,
+        position: &UciPosition,
+        limits: SearchLimits<'_>,
         should_cancel: impl Fn() -> bool,
+        mut on_info: impl FnMut(&AnalysisLine),
     ) -> Result<RawAnalysisResult, StockfishError> {
-        self.write_line(&format!("position fen {fen}"))?;
+        self.write_line(&position.command())?;
+        self.write_line(&limits.go_command())?;
 
-        let command = if disregard_think_time {
-            format!("go depth {depth}")
-        } else {
-            format!("go depth {depth} movetime {max_think_time_ms}")
-        };
-
-        self.write_line(&command)?;
-
-        let timeout = if disregard_think_time {
-            Duration::from_secs((depth as u64).saturating_mul(3).clamp(10, 90))
-        } else {
-            Duration::from_millis(
-                max_think_time_ms
-                    .saturating_add(5_000)
-                    .clamp(5_000, 120_000),
-            )
-        };
-
-        let deadline = Instant::now() + timeout;
-        let mut accumulator = AnalysisAccumulator::default();
+        let deadline = Instant::now() + limits.timeout();
+        let mut accumulator = AnalysisAccumulator::for_position(position);
         let mut stop_sent = false;
 
         loop {
@@ -153,7 +239,15 @@ impl StockfishProcess {
             }
 
             if Instant::now() >= deadline {
-                let _ = self.write_line("stop");
+                // The process is kept, so the late `bestmove` is read here
+                // rather than by the next search.
+                if self.write_line("stop").is_ok() {
+                    let _ = self.wait_for_line(
+                        |line| line.starts_with("bestmove"),
+                        STOP_GRACE,
+                        "bestmove",
+                    );
+                }
                 return Err(StockfishError::Timeout("bestmove"));
             }
 
@@ -164,13 +258,15 @@ impl StockfishProcess {
                 Ok(line) => {
                     tracing::debug!(%line, "uci");
                     if let Some(info) = parse_info_line(&line) {
-                        accumulator.update(info_to_analysis_line(info));
+                        accumulator.record_totals(&info);
+                        if let Some(line) = accumulator.update(info_to_analysis_line(info)) {
+                            on_info(line);
+                        }
                     } else if let Some((best_move, ponder)) = parse_bestmove_line(&line) {
-                        return Ok(RawAnalysisResult {
-                            best_move,
-                            ponder,
-                            lines: accumulator.into_lines(),
-                        });
+                        let best_move = best_move.ok_or(StockfishError::MissingBestMove)?;
+                        let mut result = accumulator.into_result(best_move, ponder);
+                        result.cancelled = stop_sent;
+                        return Ok(result);
                     }
                 }
                 Err(mpsc::RecvTimeoutError::Timeout) => continue,
@@ -181,6 +277,24 @@ impl StockfishProcess {
         }
     }
 
+    /// Starts a search that only ends when `stop` is sent.
+    pub fn go_infinite(&mut self, fen: &str) -> Result<(), StockfishError> {
+        self.write_line(&UciPosition::from_fen(fen).command())?;
+        self.write_line("go infinite")
+    }
+
+    /// Waits up to `timeout` for the next line of engine output.
+    pub fn read_line(&mut self, timeout: Duration) -> Result<Option<String>, StockfishError> {
+        match self.lines.recv_timeout(timeout) {
+            Ok(line) => {
+                tracing::debug!(%line, "uci");
+                Ok(Some(line))
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => Ok(None),
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(StockfishError::EngineExited),
+        }
+    }
+
     pub fn write_line(&mut self, command: &str) -> Result<(), StockfishError> {
         tracing::debug!(command, "sending uci command");
         writeln!(self.stdin, "{command}")?;
@@ -188,6 +302,10 @@ impl StockfishProcess {
         Ok(())
     }
 
+    pub fn has_exited(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(Some(_)))
+    }
+
     pub fn try_kill(&mut self) {
         let _ = self.write_line("quit");
         let _ = self.child.kill();
@@ -205,6 +323,9 @@ impl StockfishProcess {
                     if let Some(name) = line.strip_prefix("id name ") {
                         self.name = Some(name.trim().to_string());
                     }
+                    if let Some(option) = parse_option_line(&line) {
+                        self.options.push(option);
+                    }
                     if line.trim() == "uciok" {
                         saw_uciok = true;
                         break;
@@ -0,0 +1,311 @@
+use std::{cmp::Reverse, collections::BTreeMap, fs, path::PathBuf, time::SystemTime};
+
+use serde::Serialize;
+use shakmaty::{san::SanPlus, CastlingMode, Chess, Position};
+use shakmaty_syzygy::Tablebase;
+
+use crate::engine::{manager::EngineManagerError, uci::Wdl};
+
+/// Tables in a complete Syzygy set, by piece count.
+const TABLES_PER_PIECE_COUNT: [(usize, usize); 5] =
+    [(3, 5), (4, 30), (5, 110), (6, 365), (7, 1001)];
+/// Score of a tablebase win, as Stockfish reports one: above any
+/// evaluation but below mate scores.
+pub const TB_WIN_CP: i32 = 20_000;
+
+/// A tablebase result for the side to move, with the fifty-move rule applied.
+/// Ordered from worst to best for the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TablebaseWdl {
+    Loss,
+    /// Lost, but saved by the fifty-move rule.
+    BlessedLoss,
+    Draw,
+    /// Won, but only after the fifty-move rule allows a draw claim.
+    CursedWin,
+    Win,
+}
+
+impl TablebaseWdl {
+    /// The result of a position `dtz` plies from a capture or pawn move,
+    /// with `halfmoves` already on the fifty-move counter.
+    pub fn from_dtz(dtz: i32, halfmoves: u32) -> Self {
+        let within_fifty_moves = dtz.unsigned_abs() + halfmoves <= 100;
+        match (dtz.signum(), within_fifty_moves) {
+            (0, _) => TablebaseWdl::Draw,
+            (1, true) => TablebaseWdl::Win,
+            (1, false) => TablebaseWdl::CursedWin,
+            (_, true) => TablebaseWdl::Loss,
+            (_, false) => TablebaseWdl::BlessedLoss,
+        }
+    }
+
+    /// The same result for the other side.
+    pub fn flip(self) -> Self {
+        match self {
+            TablebaseWdl::Loss => TablebaseWdl::Win,
+            TablebaseWdl::BlessedLoss => TablebaseWdl::CursedWin,
+            TablebaseWdl::Draw => TablebaseWdl::Draw,
+            TablebaseWdl::CursedWin => TablebaseWdl::BlessedLoss,
+            TablebaseWdl::Win => TablebaseWdl::Loss,
+        }
+    }
+
+    /// Wins and losses are worth `TB_WIN_CP` less one per ply of `dtz`, so
+    /// quicker wins and slower losses score higher. The rest are draws.
+    pub fn score_cp(self, dtz: i32) -> i32 {
+        let distance = dtz.abs().min(TB_WIN_CP / 2);
+        match self {
+            TablebaseWdl::Win => TB_WIN_CP - distance,
+            TablebaseWdl::Loss => distance - TB_WIN_CP,
+            _ => 0,
+        }
+    }
+
+    pub fn wdl(self) -> Wdl {
+        let (win, draw, loss) = match self {
+            TablebaseWdl::Win => (1000, 0, 0),
+            TablebaseWdl::Loss => (0, 0, 1000),
+            _ => (0, 1000, 0),
+        };
+        Wdl { win, draw, loss }
+    }
+}
+
+/// A position's tablebase result, with every legal move ranked.
+#[derive(Debug, Clone, Serialize)]
+pub struct TablebaseProbe {
+    pub pieces: usize,
+    pub wdl: TablebaseWdl,
+    /// Plies until a capture or pawn move on the best line, negative when
+    /// losing. Some tables store it rounded, so it may be one ply off.
+    pub dtz: i32,
+    /// Best first: wins by distance, then draws, then the longest losses.
+    pub moves: Vec<TablebaseMove>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TablebaseMove {
+    pub uci: String,
+    pub san: String,
+    /// The result of playing the move, for the side making it.
+    pub wdl: TablebaseWdl,
+    /// `dtz` of the position if this move is played in it.
+    pub dtz: i32,
+    /// A capture or pawn move, which resets the fifty-move counter.
+    pub zeroing: bool,
+    pub checkmate: bool,
+}
+
+/// The Syzygy files found in one configured folder.
+#[derive(Debug, Clone, Serialize)]
+pub struct TablebaseFolder {
+    pub path: String,
+    /// Why the folder could not be read, if it could not.
+    pub error: Option<String>,
+    pub piece_sets: Vec<PieceSet>,
+}
+
+/// The tables of one piece count found in a folder.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PieceSet {
+    pub pieces: usize,
+    pub wdl_tables: usize,
+    pub dtz_tables: usize,
+    /// Tables in the full Syzygy set for this piece count.
+    pub expected_tables: usize,
+    pub complete: bool,
+}
+
+/// Lists the `.rtbw` (WDL) and `.rtbz` (DTZ) tables in `path` by piece count.
+pub fn scan_folder(path: &str) -> TablebaseFolder {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(err) => {
+            return TablebaseFolder {
+                path: path.to_string(),
+                error: Some(err.to_string()),
+                piece_sets: Vec::new(),
+            }
+        }
+    };
+
+    let mut counts: BTreeMap<usize, (usize, usize)> = BTreeMap::new();
+    for file in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        let Some(pieces) = file
+            .file_stem()
+            .and_then(|stem| table_pieces(&stem.to_string_lossy()))
+        else {
+            continue;
+        };
+        let count = counts.entry(pieces).or_default();
+        match file.extension().and_then(|ext| ext.to_str()) {
+            Some("rtbw") => count.0 += 1,
+            Some("rtbz") => count.1 += 1,
+            _ => {}
+        }
+    }
+
+    let piece_sets = counts
+        .into_iter()
+        .filter(|(_, (wdl_tables, dtz_tables))| wdl_tables + dtz_tables > 0)
+        .map(|(pieces, (wdl_tables, dtz_tables))| {
+            let expected_tables = TABLES_PER_PIECE_COUNT
+                .iter()
+                .find(|(count, _)| *count == pieces)
+                .map_or(0, |(_, tables)| *tables);
+            PieceSet {
+                pieces,
+                wdl_tables,
+                dtz_tables,
+                expected_tables,
+                complete: wdl_tables >= expected_tables && dtz_tables >= expected_tables,
+            }
+        })
+        .collect();
+
+    TablebaseFolder {
+        path: path.to_string(),
+        error: None,
+        piece_sets,
+    }
+}
+
+/// Pieces in a table named like `KRPvKR`, or `None` for other names.
+fn table_pieces(name: &str) -> Option<usize> {
+    let (white, black) = name.split_once('v')?;
+    let side_ok = |side: &str| {
+        side.starts_with('K')
+            && side[1..]
+                .chars()
+                .all(|piece| matches!(piece, 'Q' | 'R' | 'B' | 'N' | 'P'))
+    };
+    (side_ok(white) && side_ok(black)).then(|| white.len() + black.len())
+}
+
+/// The tables in the configured folders, reloaded when a folder changes.
+pub struct Tablebases {
+    folders: Vec<(PathBuf, Option<SystemTime>)>,
+    tables: Tablebase<Chess>,
+}
+
+impl Default for Tablebases {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tablebases {
+    pub fn new() -> Self {
+        Self {
+            folders: Vec::new(),
+            tables: Tablebase::new(),
+        }
+    }
+
+    /// Most pieces of any table in `paths`; 0 when there are none.
+    pub fn max_pieces(&mut self, paths: &[String]) -> usize {
+        self.refresh(paths);
+        self.tables.max_pieces()
+    }
+
+    /// Probes `position` and every move from it, or returns `None` when the
+    /// tables in `paths` do not cover it.
+    pub fn probe(
+        &mut self,
+        paths: &[String],
+        position: &Chess,
+    ) -> Result<Option<TablebaseProbe>, EngineManagerError> {
+        self.refresh(paths);
+        let pieces = position.board().occupied().count();
+        if pieces > self.tables.max_pieces() || position.castles().any() {
+            return Ok(None);
+        }
+
+        let (wdl, dtz) = self.outcome(position)?;
+        let mut moves = Vec::new();
+        for legal in position.legal_moves() {
+            let mut after = position.clone();
+            after.play_unchecked(legal);
+            let checkmate = after.is_checkmate();
+            let (reply_wdl, reply_dtz) = self.outcome(&after)?;
+            let wdl = reply_wdl.flip();
+            let dtz = if checkmate || legal.is_zeroing() {
+                wdl_sign(wdl)
+            } else {
+                wdl_sign(wdl) * (reply_dtz.abs() + 1)
+            };
+            moves.push(TablebaseMove {
+                uci: legal.to_uci(CastlingMode::Standard).to_string(),
+                san: SanPlus::from_move(position.clone(), legal).to_string(),
+                wdl,
+                dtz,
+                zeroing: legal.is_zeroing(),
+                checkmate,
+            });
+        }
+        moves
+            .sort_by_key(|candidate| (Reverse(candidate.wdl), !candidate.checkmate, candidate.dtz));
+
+        Ok(Some(TablebaseProbe {
+            pieces,
+            wdl,
+            dtz,
+            moves,
+        }))
+    }
+
+    /// The result and DTZ for the side to move. Finished games are answered
+    /// without the tables, which have no entries for them.
+    fn outcome(&self, position: &Chess) -> Result<(TablebaseWdl, i32), EngineManagerError> {
+        if position.is_checkmate() {
+            return Ok((TablebaseWdl::Loss, 0));
+        }
+        if position.is_stalemate() || position.is_insufficient_material() {
+            return Ok((TablebaseWdl::Draw, 0));
+        }
+        let dtz = self
+            .tables
+            .probe_dtz(position)
+            .map_err(|err| EngineManagerError::Tablebase(err.to_string()))?
+            .ignore_rounding()
+            .0;
+        Ok((TablebaseWdl::from_dtz(dtz, position.halfmoves()), dtz))
+    }
+
+    /// Reloads the tables when the folders or their contents changed. A
+    /// folder that cannot be read is skipped; `scan_folder` reports why.
+    fn refresh(&mut self, paths: &[String]) {
+        let folders: Vec<_> = paths
+            .iter()
+            .map(|path| {
+                let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+                (PathBuf::from(path), modified)
+            })
+            .collect();
+        if folders == self.folders {
+            return;
+        }
+
+        let mut tables = Tablebase::new();
+        for (path, _) in &folders {
+            match tables.add_directory(path) {
+                Ok(added) => tracing::info!(path = %path.display(), added, "loaded Syzygy tables"),
+                Err(err) => {
+                    tracing::warn!(path = %path.display(), %err, "could not read Syzygy tables")
+                }
+            }
+        }
+        self.tables = tables;
+        self.folders = folders;
+    }
+}
+
+fn wdl_sign(wdl: TablebaseWdl) -> i32 {
+    match wdl {
+        TablebaseWdl::Win | TablebaseWdl::CursedWin => 1,
+        TablebaseWdl::Draw => 0,
+        _ => -1,
+    }
+}
@@ -0,0 +1,63 @@
+use serde::Serialize;
+use shakmaty::{fen::Fen, Chess, EnPassantMode, Position};
+
+use crate::engine::{
+    analysis::RawAnalysisResult,
+    notation::{self, MoveDetail},
+};
+
+/// The move the opponent would play if the side to move passed.
+#[derive(Debug, Clone, Serialize)]
+pub struct Threat {
+    /// The position searched: the analysed one with the other side to move.
+    pub fen: String,
+    pub move_uci: String,
+    pub move_san: String,
+    pub move_detail: MoveDetail,
+    /// The opponent's score after the threat search.
+    pub score_cp: Option<i32>,
+    pub mate: Option<i32>,
+    /// Centipawns the opponent gains by getting the extra move, measured
+    /// against the analysed position's best line. `None` when either score
+    /// is a mate or missing.
+    pub gain_cp: Option<i32>,
+    pub depth: Option<u32>,
+    pub pv: Vec<String>,
+    pub pv_san: Vec<String>,
+}
+
+/// The position with the other side to move, or `None` when the side to
+/// move is in check and cannot pass.
+pub fn null_move(position: &Chess) -> Option<Chess> {
+    position.clone().swap_turn().ok()
+}
+
+/// Reads the threat from a search of the `null_move` position. `current_cp`
+/// is the analysed position's score for the side to move.
+pub fn from_search(
+    null_position: &Chess,
+    raw: &RawAnalysisResult,
+    current_cp: Option<i32>,
+) -> Option<Threat> {
+    let line = raw
+        .lines
+        .iter()
+        .find(|line| line.move_uci.as_deref() == Some(raw.best_move.as_str()))
+        .or_else(|| raw.lines.first());
+    let score_cp = line.and_then(|line| line.score_cp);
+    let mate = line.and_then(|line| line.mate);
+    let detail = notation::describe_move(null_position, &raw.best_move)?;
+
+    Some(Threat {
+        fen: Fen::from_position(null_position, EnPassantMode::Legal).to_string(),
+        move_uci: raw.best_move.clone(),
+        move_san: detail.san.clone(),
+        move_detail: detail,
+        score_cp,
+        mate,
+        gain_cp: score_cp.zip(current_cp).map(|(after, now)| after + now),
+        depth: line.and_then(|line| line.depth),
+        pv: line.map(|line| line.pv.clone()).unwrap_or_default(),
+        pv_san: line.map(|line| line.pv_san.clone()).unwrap_or_default(),
+    })
+}
@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 use crate::api::types::AnalysisLine;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -6,11 +8,69 @@ pub enum UciScore {
     Mate(i32),
 }
 
-#[derive(Debug, Clone)]
+/// Marks a score from a search that failed high (`lowerbound`) or low
+/// (`upperbound`), so the real score is only known to be on one side of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreBound {
+    Lower,
+    Upper,
+}
+
+/// Win/draw/loss expectation in per mille, from the side to move's point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Wdl {
+    pub win: u32,
+    pub draw: u32,
+    pub loss: u32,
+}
+
+/// The position part of a UCI `position` command: a start FEN plus the
+/// moves played from it, so the engine sees the game history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UciPosition {
+    pub fen: String,
+    pub moves: Vec<String>,
+}
+
+impl UciPosition {
+    pub fn from_fen(fen: impl Into<String>) -> Self {
+        Self {
+            fen: fen.into(),
+            moves: Vec::new(),
+        }
+    }
+
+    /// Side to move after `moves`, read from the FEN's active colour field.
+    pub fn white_to_move(&self) -> bool {
+        let white_starts = self.fen.split_whitespace().nth(1) != Some("b");
+        white_starts == self.moves.len().is_multiple_of(2)
+    }
+
+    pub fn command(&self) -> String {
+        if self.moves.is_empty() {
+            format!("position fen {}", self.fen)
+        } else {
+            format!("position fen {} moves {}", self.fen, self.moves.join(" "))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct ParsedInfo {
     pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
     pub multipv: u8,
     pub score: Option<UciScore>,
+    pub bound: Option<ScoreBound>,
+    pub wdl: Option<Wdl>,
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    pub time_ms: Option<u64>,
+    pub hashfull: Option<u32>,
+    pub tbhits: Option<u64>,
+    pub currmove: Option<String>,
+    pub currmovenumber: Option<u32>,
     pub pv: Vec<String>,
 }
 
@@ -20,61 +80,255 @@ pub fn parse_info_line(line: &str) -> Option<ParsedInfo> {
     }
 
     let parts: Vec<&str> = line.split_whitespace().collect();
-    let mut depth = None;
-    let mut multipv = 1_u8;
-    let mut score = None;
-    let mut pv = Vec::new();
+    let value = |i: usize| parts.get(i + 1).copied().unwrap_or_default();
+    let mut info = ParsedInfo {
+        multipv: 1,
+        ..ParsedInfo::default()
+    };
 
     let mut i = 1;
     while i < parts.len() {
         match parts[i] {
-            "depth" if i + 1 < parts.len() => {
-                depth = parts[i + 1].parse::<u32>().ok();
+            "depth" => {
+                info.depth = value(i).parse().ok();
+                i += 2;
+            }
+            "seldepth" => {
+                info.seldepth = value(i).parse().ok();
                 i += 2;
             }
-            "multipv" if i + 1 < parts.len() => {
-                multipv = parts[i + 1].parse::<u8>().unwrap_or(1).max(1);
+            "multipv" => {
+                info.multipv = value(i).parse::<u8>().unwrap_or(1).max(1);
                 i += 2;
             }
             "score" if i + 2 < parts.len() => {
-                score = match parts[i + 1] {
+                info.score = match parts[i + 1] {
                     "cp" => parts[i + 2].parse::<i32>().ok().map(UciScore::Cp),
                     "mate" => parts[i + 2].parse::<i32>().ok().map(UciScore::Mate),
                     _ => None,
                 };
                 i += 3;
             }
+            "lowerbound" => {
+                info.bound = Some(ScoreBound::Lower);
+                i += 1;
+            }
+            "upperbound" => {
+                info.bound = Some(ScoreBound::Upper);
+                i += 1;
+            }
+            "wdl" if i + 3 < parts.len() => {
+                info.wdl = match (
+                    parts[i + 1].parse(),
+                    parts[i + 2].parse(),
+                    parts[i + 3].parse(),
+                ) {
+                    (Ok(win), Ok(draw), Ok(loss)) => Some(Wdl { win, draw, loss }),
+                    _ => None,
+                };
+                i += 4;
+            }
+            "nodes" => {
+                info.nodes = value(i).parse().ok();
+                i += 2;
+            }
+            "nps" => {
+                info.nps = value(i).parse().ok();
+                i += 2;
+            }
+            "time" => {
+                info.time_ms = value(i).parse().ok();
+                i += 2;
+            }
+            "hashfull" => {
+                info.hashfull = value(i).parse().ok();
+                i += 2;
+            }
+            "tbhits" => {
+                info.tbhits = value(i).parse().ok();
+                i += 2;
+            }
+            "currmove" if i + 1 < parts.len() => {
+                info.currmove = Some(parts[i + 1].to_string());
+                i += 2;
+            }
+            "currmovenumber" => {
+                info.currmovenumber = value(i).parse().ok();
+                i += 2;
+            }
             "pv" => {
-                pv = parts[i + 1..].iter().map(|s| s.to_string()).collect();
+                info.pv = parts[i + 1..].iter().map(|s| s.to_string()).collect();
                 break;
             }
+            // Free text for the GUI; nothing after it is a keyword.
+            "string" => break,
             _ => i += 1,
         }
     }
 
-    Some(ParsedInfo {
-        depth,
-        multipv,
-        score,
-        pv,
+    Some(info)
+}
+
+/// One entry of the option catalogue an engine prints before `uciok`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UciOption {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: UciOptionKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UciOptionKind {
+    Spin {
+        default: i64,
+        min: i64,
+        max: i64,
+    },
+    Check {
+        default: bool,
+    },
+    Combo {
+        default: String,
+        values: Vec<String>,
+    },
+    String {
+        default: String,
+    },
+    Button,
+}
+
+impl UciOption {
+    /// Checks that `value` is something `setoption` will accept for this option.
+    pub fn check_value(&self, value: &str) -> Result<(), String> {
+        let name = &self.name;
+        match &self.kind {
+            UciOptionKind::Spin { min, max, .. } => match value.trim().parse::<i64>() {
+                Ok(number) if (*min..=*max).contains(&number) => Ok(()),
+                _ => Err(format!(
+                    "{name} must be a whole number between {min} and {max}"
+                )),
+            },
+            UciOptionKind::Check { .. } => match value {
+                "true" | "false" => Ok(()),
+                _ => Err(format!("{name} must be true or false")),
+            },
+            UciOptionKind::Combo { values, .. } => {
+                if values
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(value))
+                {
+                    Ok(())
+                } else {
+                    Err(format!("{name} must be one of: {}", values.join(", ")))
+                }
+            }
+            UciOptionKind::String { .. } => Ok(()),
+            UciOptionKind::Button => Err(format!("{name} is a button, not a setting")),
+        }
+    }
+}
+
+/// Parses `option name <name> type <type> [default ..] [min ..] [max ..] [var ..]*`.
+///
+/// Names, defaults and combo values may contain spaces, so each field runs
+/// until the next keyword.
+pub fn parse_option_line(line: &str) -> Option<UciOption> {
+    let rest = line.strip_prefix("option ")?;
+
+    let mut name = Vec::new();
+    let mut kind = None;
+    let mut default: Option<Vec<&str>> = None;
+    let mut min = None;
+    let mut max = None;
+    let mut values: Vec<Vec<&str>> = Vec::new();
+
+    let mut field = "";
+    for token in rest.split_whitespace() {
+        match token {
+            "name" | "type" | "default" | "min" | "max" | "var" => {
+                field = token;
+                match token {
+                    "default" => default = Some(Vec::new()),
+                    "var" => values.push(Vec::new()),
+                    _ => {}
+                }
+            }
+            _ => match field {
+                "name" => name.push(token),
+                "type" => kind = Some(token),
+                "default" => default.get_or_insert_with(Vec::new).push(token),
+                "min" => min = token.parse::<i64>().ok(),
+                "max" => max = token.parse::<i64>().ok(),
+                "var" => {
+                    if let Some(value) = values.last_mut() {
+                        value.push(token);
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+
+    if name.is_empty() {
+        return None;
+    }
+    // Stockfish prints an empty string default as `<empty>`.
+    let default = default
+        .map(|words| words.join(" "))
+        .filter(|value| value != "<empty>")
+        .unwrap_or_default();
+
+    let kind = match kind? {
+        "spin" => UciOptionKind::Spin {
+            default: default.parse().ok()?,
+            min: min?,
+            max: max?,
+        },
+        "check" => UciOptionKind::Check {
+            default: default == "true",
+        },
+        "combo" => UciOptionKind::Combo {
+            default,
+            values: values.into_iter().map(|words| words.join(" ")).collect(),
+        },
+        "string" => UciOptionKind::String { default },
+        "button" => UciOptionKind::Button,
+        _ => return None,
+    };
+
+    Some(UciOption {
+        name: name.join(" "),
+        kind,
     })
 }
 
-pub fn parse_bestmove_line(line: &str) -> Option<(String, Option<String>)> {
+/// Parses `bestmove <move> [ponder <move>]`.
+///
+/// Engines answer `bestmove (none)` or `bestmove 0000` when there is no legal
+/// move; those come back as `None` rather than as a move string.
+pub fn parse_bestmove_line(line: &str) -> Option<(Option<String>, Option<String>)> {
     let parts: Vec<&str> = line.split_whitespace().collect();
     if parts.first().copied() != Some("bestmove") || parts.len() < 2 {
         return None;
     }
 
-    let best_move = parts[1].to_string();
+    let best_move = real_move(parts[1]);
     let ponder = parts
         .windows(2)
         .find(|window| window[0] == "ponder")
-        .map(|window| window[1].to_string());
+        .and_then(|window| real_move(window[1]));
 
     Some((best_move, ponder))
 }
 
+fn real_move(uci: &str) -> Option<String> {
+    match uci {
+        "(none)" | "0000" => None,
+        _ => Some(uci.to_string()),
+    }
+}
+
 pub fn info_to_analysis_line(info: ParsedInfo) -> AnalysisLine {
     let (score_cp, mate) = match info.score {
         Some(UciScore::Cp(cp)) => (Some(cp), None),
@@ -87,10 +341,26 @@ pub fn info_to_analysis_line(info: ParsedInfo) -> AnalysisLine {
     AnalysisLine {
         rank: info.multipv,
         depth: info.depth,
+        seldepth: info.seldepth,
         move_uci,
         score_cp,
         mate,
+        bound: info.bound,
+        wdl: info.wdl,
+        white_score_cp: None,
+        white_mate: None,
+        white_wdl: None,
+        nodes: info.nodes,
+        nps: info.nps,
+        time_ms: info.time_ms,
+        hashfull: info.hashfull,
+        tbhits: info.tbhits,
         pv: info.pv,
+        move_san: None,
+        move_detail: None,
+        pv_san: Vec::new(),
+        pv_text: None,
+        pv_illegal_index: None,
     }
 }
 
@@ -105,6 +375,8 @@ mod tests {
                 .unwrap();
 
         assert_eq!(parsed.depth, Some(12));
+        assert_eq!(parsed.seldepth, Some(18));
+        assert_eq!(parsed.nodes, Some(1));
         assert_eq!(parsed.multipv, 2);
         assert_eq!(parsed.score, Some(UciScore::Cp(31)));
         assert_eq!(parsed.pv, vec!["e2e4", "e7e5"]);
@@ -113,7 +385,7 @@ mod tests {
     #[test]
     fn parses_bestmove() {
         let parsed = parse_bestmove_line("bestmove e2e4 ponder e7e5").unwrap();
-        assert_eq!(parsed.0, "e2e4");
+        assert_eq!(parsed.0.as_deref(), Some("e2e4"));
         assert_eq!(parsed.1.as_deref(), Some("e7e5"));
     }
 }
@@ -0,0 +1,75 @@
+use crate::{api::types::AnalysisLine, engine::uci::Wdl};
+
+/// Centipawn score at which the model gives a 50% chance of winning.
+///
+/// Stockfish normalises its evaluations so that +100 cp means a 50% win
+/// chance in a typical middlegame, so the same anchor is used here.
+const WIN_RATE_CENTER_CP: f64 = 100.0;
+/// Spread of the logistic curve, in centipawns. Smaller values make the
+/// probabilities swing faster as the score moves away from the centre.
+const WIN_RATE_SCALE_CP: f64 = 60.0;
+
+/// Estimates win/draw/loss per mille for the side to move from a score.
+///
+/// The model treats winning and losing as two mirrored logistic curves:
+///
+/// ```text
+/// win  = 1000 / (1 + exp((100 - cp) / 60))
+/// loss = 1000 / (1 + exp((100 + cp) / 60))
+/// draw = 1000 - win - loss
+/// ```
+///
+/// A mate for the side to move is a certain win and a mate against it a
+/// certain loss. It is only used when the engine does not report `wdl` itself.
+pub fn estimate(score_cp: Option<i32>, mate: Option<i32>) -> Option<Wdl> {
+    if let Some(mate) = mate {
+        return Some(if mate > 0 {
+            Wdl {
+                win: 1000,
+                draw: 0,
+                loss: 0,
+            }
+        } else {
+            Wdl {
+                win: 0,
+                draw: 0,
+                loss: 1000,
+            }
+        });
+    }
+
+    let cp = f64::from(score_cp?);
+    let curve =
+        |offset: f64| 1000.0 / (1.0 + ((WIN_RATE_CENTER_CP + offset) / WIN_RATE_SCALE_CP).exp());
+    let win = curve(-cp).round() as u32;
+    let loss = curve(cp).round() as u32;
+
+    Some(Wdl {
+        win,
+        draw: 1000u32.saturating_sub(win + loss),
+        loss,
+    })
+}
+
+/// Fills in the model WDL when the engine gave none, and the White
+/// point-of-view copies of the score.
+pub fn complete_line(line: &mut AnalysisLine, white_to_move: bool) {
+    if line.wdl.is_none() {
+        line.wdl = estimate(line.score_cp, line.mate);
+    }
+
+    let sign = if white_to_move { 1 } else { -1 };
+    line.white_score_cp = line.score_cp.map(|cp| cp * sign);
+    line.white_mate = line.mate.map(|mate| mate * sign);
+    line.white_wdl = line.wdl.map(|wdl| {
+        if white_to_move {
+            wdl
+        } else {
+            Wdl {
+                win: wdl.loss,
+                draw: wdl.draw,
+                loss: wdl.win,
+            }
+        }
+    });
+}
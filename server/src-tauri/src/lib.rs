@@ -12,10 +12,12 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilte
 use crate::{
     app_state::AppState,
     commands::{
-        cancel_analysis, choose_stockfish_manually, choose_syzygy_folders, clear_syzygy_folders,
-        detect_stockfish, download_stockfish, get_history, get_settings, get_ui_status,
+        cancel_analysis, cancel_analysis_request, choose_stockfish_manually, choose_syzygy_folders,
+        clear_analysis_cache, clear_syzygy_folders, detect_stockfish, download_stockfish,
+        get_analysis_cache, get_engine_options, get_history, get_settings, get_ui_status,
         redownload_engine, redownload_stockfish, reset_recommended_settings, reset_settings,
-        restart_engine, save_settings, set_timing_preset, test_connection, update_settings,
+        restart_engine, save_settings, set_engine_profile, set_timing_preset, test_connection,
+        update_settings,
     },
     config::store::ConfigStore,
     engine::manager::{EngineManager, EngineManagerError},
@@ -47,6 +49,8 @@ pub fn run() {
         .manage(state)
         .setup(move |_app| {
             tauri::async_runtime::spawn(async move {
+                setup_state.engine.spawn_supervisor();
+
                 match setup_state.engine.initialize_from_config().await {
                     Ok(()) => info!("chess engine initialized"),
                     Err(EngineManagerError::NotConfigured) => {
@@ -68,7 +72,12 @@ pub fn run() {
             update_settings,
             set_timing_preset,
             restart_engine,
+            get_engine_options,
+            set_engine_profile,
             cancel_analysis,
+            cancel_analysis_request,
+            get_analysis_cache,
+            clear_analysis_cache,
             detect_stockfish,
             choose_stockfish_manually,
             download_stockfish,
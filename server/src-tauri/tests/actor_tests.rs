@@ -0,0 +1,87 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use roblox_chess_script_lib::{
+    api::types::AnalyzeRequest,
+    engine::{manager::EngineManagerError, stockfish::StockfishError},
+};
+
+use common::START;
+
+#[tokio::test]
+async fn status_does_not_wait_for_a_running_search() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let engine = common::fake_engine(
+        dir.path(),
+        "go*) sleep 2; echo 'info depth 1 score cp 20 pv e2e4'; echo 'bestmove e2e4' ;;",
+    );
+    let manager = common::manager(dir.path(), engine, |_| {}).await;
+
+    let searching = manager.clone();
+    let search = tokio::spawn(async move {
+        searching
+            .analyze(AnalyzeRequest {
+                fen: Some(START.to_string()),
+                request_id: Some("slow-search".to_string()),
+                depth: Some(5),
+                max_think_time_ms: Some(5000),
+                ..Default::default()
+            })
+            .await
+    });
+
+    let deadline = Instant::now() + Duration::from_secs(1);
+    while manager.status().status != "analyzing" {
+        assert!(Instant::now() < deadline, "the search never started");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    // The actor is busy with the search, so an answer now has to come from
+    // the snapshot it publishes.
+    let asked = Instant::now();
+    let status = manager.status();
+    assert!(asked.elapsed() < Duration::from_millis(500));
+    assert!(!search.is_finished());
+    assert_eq!(status.status, "analyzing");
+    assert_eq!(status.current_job_id.as_deref(), Some("slow-search"));
+
+    let response = search.await.expect("search task").expect("analysis");
+    assert_eq!(response.best_move.as_deref(), Some("e2e4"));
+    assert_eq!(manager.status().status, "ready");
+}
+
+#[tokio::test]
+async fn a_timed_out_search_does_not_answer_the_next_one() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    // The first search only ends on `stop`; later ones answer at once.
+    let answered = dir.path().join("answered");
+    let engine = common::fake_engine(
+        dir.path(),
+        &format!(
+            "go*) if [ -e '{0}' ]; then echo 'info depth 1 score cp 20 pv e2e4'; \
+             echo 'bestmove e2e4'; else touch '{0}'; fi ;;\n\
+             stop) echo 'bestmove a2a3' ;;",
+            answered.display()
+        ),
+    );
+    let manager = common::manager(dir.path(), engine, |_| {}).await;
+    let request = |new_game| AnalyzeRequest {
+        fen: Some(START.to_string()),
+        depth: Some(5),
+        max_think_time_ms: Some(10),
+        new_game: Some(new_game),
+        ..Default::default()
+    };
+
+    let timed_out = manager.analyze(request(true)).await;
+    assert!(matches!(
+        timed_out,
+        Err(EngineManagerError::Stockfish(StockfishError::Timeout(_)))
+    ));
+
+    let response = manager.analyze(request(false)).await.expect("analysis");
+    assert_eq!(response.best_move.as_deref(), Some("e2e4"));
+}
@@ -1,3 +1,6 @@
+#[cfg(unix)]
+mod common;
+
 use axum::{
     body::Body,
     http::{Request, StatusCode},
@@ -54,6 +57,7 @@ async fn status_endpoint_returns_default_not_configured_state() {
     assert_eq!(status, StatusCode::OK);
     assert_eq!(value["ok"], true);
     assert_eq!(value["engine"]["status"], "not_configured");
+    assert_eq!(value["engine"]["restart_count"], 0);
     assert_eq!(value["config"]["server"]["port"], 57250);
 }
 
@@ -103,6 +107,344 @@ async fn analyze_returns_engine_not_configured_for_valid_fen_without_stockfish()
     assert_eq!(value["error"]["code"], "engine_not_configured");
 }
 
+#[tokio::test]
+async fn analyze_rejects_illegal_move_in_move_list() {
+    let (_dir, app) = test_app();
+    let body = json!({
+        "moves": ["e2e4", "e7e5", "e1e3"],
+        "depth": 12,
+        "max_think_time_ms": 100,
+        "disregard_think_time": false
+    });
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/v1/analyze")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+
+    let (status, value) = json_response(app, request).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(value["error"]["code"], "invalid_request");
+    assert!(value["error"]["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("moves[2] (e1e3)"));
+}
+
+#[tokio::test]
+async fn analyze_reports_game_over_without_an_engine() {
+    let (_dir, app) = test_app();
+    // Fool's mate: White is checkmated.
+    let body = json!({
+        "moves": ["f2f3", "e7e5", "g2g4", "d8h4"],
+        "depth": 12,
+        "max_think_time_ms": 100
+    });
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/v1/analyze")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+
+    let (status, value) = json_response(app, request).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(value["best_move"], Value::Null);
+    assert_eq!(value["game_over"]["outcome"], "black_wins");
+    assert_eq!(value["game_over"]["reason"], "checkmate");
+    assert_eq!(value["lines"], json!([]));
+}
+
+#[tokio::test]
+async fn opening_book_answers_without_the_engine() {
+    let (dir, store) = temp_store();
+    let book = dir.path().join("book.bin");
+    // Polyglot entry for 1. e4 in the start position.
+    let mut entry = 0x463b_9618_1691_fc9c_u64.to_be_bytes().to_vec();
+    entry.extend([0x03, 0x1c, 0x00, 0x05, 0, 0, 0, 0]);
+    std::fs::write(&book, entry).unwrap();
+    let mut config = store.load_or_default();
+    config.analysis.book.enabled = true;
+    config.analysis.book.paths = vec![book.display().to_string()];
+    store.save(&config).unwrap();
+    let engine = EngineManager::new(store.clone());
+    let app = Router::new()
+        .nest("/api/v1", api_routes())
+        .with_state(AppState::new(store, engine));
+
+    let analyze = |body: Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/analyze")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    let (status, value) = json_response(app.clone(), analyze(json!({ "fen": start }))).await;
+    assert_eq!(status, StatusCode::OK, "{value}");
+    assert_eq!(value["source"], "book");
+    assert_eq!(value["best_move"], "e2e4");
+    assert_eq!(value["book_moves"][0]["weight"], 5);
+    assert_eq!(value["lines"][0]["move_san"], "e4");
+
+    let (status, value) =
+        json_response(app, analyze(json!({ "fen": start, "use_book": false }))).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(value["error"]["code"], "engine_not_configured");
+}
+
+#[tokio::test]
+async fn a_missing_opening_book_falls_back_to_the_engine() {
+    let (dir, store) = temp_store();
+    let mut config = store.load_or_default();
+    config.analysis.book.enabled = true;
+    config.analysis.book.paths = vec![dir.path().join("missing.bin").display().to_string()];
+    store.save(&config).unwrap();
+    let engine = EngineManager::new(store.clone());
+    let app = Router::new()
+        .nest("/api/v1", api_routes())
+        .with_state(AppState::new(store, engine));
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/v1/analyze")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "fen": "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1" })
+                .to_string(),
+        ))
+        .unwrap();
+    let (status, value) = json_response(app, request).await;
+    // The search is reached, so the missing book did not fail the request.
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE, "{value}");
+    assert_eq!(value["error"]["code"], "engine_not_configured");
+}
+
+#[tokio::test]
+async fn tablebase_endpoints_report_folders_and_uncovered_positions() {
+    let (dir, store) = temp_store();
+    let tables = dir.path().join("syzygy");
+    std::fs::create_dir(&tables).unwrap();
+    // Empty stand-ins: enough for the folder report, unreadable for probing.
+    std::fs::write(tables.join("KQvK.rtbw"), b"").unwrap();
+    std::fs::write(tables.join("KQvK.rtbz"), b"").unwrap();
+    let missing = dir.path().join("missing");
+    let mut config = store.load_or_default();
+    config.engine.syzygy_paths = vec![tables.display().to_string(), missing.display().to_string()];
+    store.save(&config).unwrap();
+    let engine = EngineManager::new(store.clone());
+    let app = Router::new()
+        .nest("/api/v1", api_routes())
+        .with_state(AppState::new(store, engine));
+    let get = |uri: &str| {
+        Request::builder()
+            .method("GET")
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let (status, value) = json_response(app.clone(), get("/api/v1/tablebase/folders")).await;
+    assert_eq!(status, StatusCode::OK, "{value}");
+    let found = &value["folders"][0];
+    assert_eq!(found["error"], Value::Null);
+    assert_eq!(found["piece_sets"][0]["pieces"], 3);
+    assert_eq!(found["piece_sets"][0]["wdl_tables"], 1);
+    assert_eq!(found["piece_sets"][0]["expected_tables"], 5);
+    assert_eq!(found["piece_sets"][0]["complete"], false);
+    assert!(value["folders"][1]["error"].is_string());
+
+    let (status, value) = json_response(app.clone(), get("/api/v1/tablebase")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(value["error"]["code"], "invalid_request");
+
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR%20w%20KQkq%20-%200%201";
+    let (status, value) =
+        json_response(app.clone(), get(&format!("/api/v1/tablebase?fen={start}"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(value["error"]["code"], "not_in_tablebase");
+
+    // The tables cannot answer, so the analysis falls through to the engine.
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/v1/analyze")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "fen": "8/8/8/8/8/2k5/8/KQ6 w - - 0 1" }).to_string(),
+        ))
+        .unwrap();
+    let (status, value) = json_response(app, request).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(value["error"]["code"], "engine_not_configured");
+}
+
+#[tokio::test]
+async fn batches_answer_every_position_in_order() {
+    let (_dir, app) = test_app();
+    let batch = |body: Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/analyze/batch")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let epd = "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - id \"fools mate\";\n\
+               not a position\n\
+               rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -\n";
+    let (status, value) =
+        json_response(app.clone(), batch(json!({ "epd": epd, "depth": 8 }))).await;
+    assert_eq!(status, StatusCode::OK, "{value}");
+    assert_eq!(value["total"], 3);
+    assert_eq!(value["succeeded"], 1);
+    assert_eq!(value["failed"], 2);
+
+    let results = value["results"].as_array().unwrap();
+    let indexes: Vec<_> = results.iter().map(|item| item["index"].clone()).collect();
+    assert_eq!(indexes, [0, 1, 2]);
+    assert_eq!(results[0]["id"], "fools mate");
+    assert_eq!(results[0]["result"]["source"], "game_over");
+    assert_eq!(results[0]["result"]["depth"], 8);
+    assert_eq!(results[0]["error"], Value::Null);
+    assert_eq!(results[1]["error"]["code"], "invalid_fen");
+    assert_eq!(results[2]["error"]["code"], "engine_not_configured");
+
+    let (status, value) = json_response(
+        app.clone(),
+        batch(json!({ "positions": [{ "moves": ["e2e4"] }], "epd": epd })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(value["error"]["code"], "invalid_request");
+
+    let (status, value) = json_response(app, batch(json!({ "positions": [] }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(value["error"]["code"], "invalid_request");
+}
+
+#[tokio::test]
+async fn evaluate_move_checks_the_move_before_searching() {
+    let (_dir, app) = test_app();
+    let evaluate = |body: Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/evaluate-move")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let (status, value) = json_response(
+        app.clone(),
+        evaluate(json!({ "moves": ["e2e4"], "move": "Ke7" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(value["error"]["code"], "invalid_request");
+
+    let (status, value) =
+        json_response(app, evaluate(json!({ "moves": ["e2e4"], "move": "Nf6" }))).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE, "{value}");
+    assert_eq!(value["error"]["code"], "engine_not_configured");
+}
+
+#[tokio::test]
+async fn position_reports_need_no_engine() {
+    let (_dir, app) = test_app();
+    let position = |body: Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/position")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let (status, value) =
+        json_response(app.clone(), position(json!({ "moves": ["e2e4", "e7e5"] }))).await;
+    assert_eq!(status, StatusCode::OK, "{value}");
+    assert_eq!(
+        value["fen"],
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2"
+    );
+    assert_eq!(value["opening"]["eco"], "C20");
+    assert_eq!(value["game_over"], Value::Null);
+    assert_eq!(value["position_report"]["legal_move_count"], 29);
+    assert_eq!(value["position_report"]["phase"], "opening");
+    assert_eq!(value["position_report"]["side_to_move"], "white");
+
+    let (status, value) = json_response(app, position(json!({}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(value["error"]["code"], "invalid_request");
+}
+
+#[tokio::test]
+async fn analyses_and_history_name_the_opening() {
+    let (_dir, app) = test_app();
+    let body = json!({
+        "moves": ["e2e4", "e7e5", "d1h5", "b8c6", "f1c4", "g8f6", "h5f7"]
+    });
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/v1/analyze")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+
+    let (status, value) = json_response(app.clone(), request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(value["game_over"]["reason"], "checkmate");
+    assert_eq!(value["opening"]["eco"], "C20");
+    assert_eq!(value["opening"]["name"], "King's Pawn Game");
+    assert_eq!(value["opening"]["ply"], 2);
+    assert_eq!(value["position_report"]["in_check"], true);
+    assert_eq!(value["position_report"]["material"]["balance"], 1);
+
+    let history = Request::builder()
+        .method("GET")
+        .uri("/api/v1/history")
+        .body(Body::empty())
+        .unwrap();
+    let (_, value) = json_response(app, history).await;
+    assert_eq!(value[0]["opening"]["eco"], "C20");
+}
+
+#[tokio::test]
+async fn engine_options_require_a_configured_engine() {
+    let (_dir, app) = test_app();
+    let request = Request::builder()
+        .uri("/api/v1/engine/options")
+        .body(Body::empty())
+        .unwrap();
+
+    let (status, value) = json_response(app, request).await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(value["error"]["code"], "engine_not_configured");
+}
+
+#[tokio::test]
+async fn switching_to_unknown_engine_profile_is_rejected() {
+    let (_dir, app) = test_app();
+    let request = Request::builder()
+        .method("PUT")
+        .uri("/api/v1/engine/profile")
+        .header("content-type", "application/json")
+        .body(Body::from(json!({ "profile": "berserk" }).to_string()))
+        .unwrap();
+
+    let (status, value) = json_response(app, request).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(value["error"]["code"], "invalid_request");
+}
+
 #[tokio::test]
 async fn cancel_endpoint_is_idempotent() {
     let (_dir, app) = test_app();
@@ -116,6 +458,160 @@ async fn cancel_endpoint_is_idempotent() {
 
     assert_eq!(status, StatusCode::OK);
     assert_eq!(value["ok"], true);
+    assert_eq!(value["cancelled"], json!([]));
+}
+
+#[tokio::test]
+async fn cancelling_unknown_request_id_is_not_found() {
+    let (_dir, app) = test_app();
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/v1/analyze/missing-request/cancel")
+        .body(Body::empty())
+        .unwrap();
+
+    let (status, value) = json_response(app, request).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(value["error"]["code"], "job_not_found");
+}
+
+#[tokio::test]
+async fn jobs_run_in_the_background_and_keep_their_result() {
+    let (_dir, app) = test_app();
+    let body = json!({
+        "request_id": "mate-job",
+        "fen": "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3"
+    });
+    let submit = Request::builder()
+        .method("POST")
+        .uri("/api/v1/jobs")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+
+    let (status, value) = json_response(app.clone(), submit).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(value["job_id"], "mate-job");
+
+    let mut job = Value::Null;
+    for _ in 0..50 {
+        let poll = Request::builder()
+            .method("GET")
+            .uri("/api/v1/jobs/mate-job")
+            .body(Body::empty())
+            .unwrap();
+        let (status, value) = json_response(app.clone(), poll).await;
+        assert_eq!(status, StatusCode::OK);
+        job = value;
+        if job["state"] == "completed" {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    assert_eq!(job["state"], "completed");
+    assert_eq!(job["result"]["game_over"]["reason"], "checkmate");
+    assert!(job["finished_at"].is_string());
+}
+
+#[tokio::test]
+async fn jobs_validate_the_request_up_front() {
+    let (_dir, app) = test_app();
+    let submit = Request::builder()
+        .method("POST")
+        .uri("/api/v1/jobs")
+        .header("content-type", "application/json")
+        .body(Body::from(json!({ "fen": "not a fen" }).to_string()))
+        .unwrap();
+
+    let (status, value) = json_response(app.clone(), submit).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(value["error"]["code"], "invalid_fen");
+
+    let missing = Request::builder()
+        .method("DELETE")
+        .uri("/api/v1/jobs/unknown")
+        .body(Body::empty())
+        .unwrap();
+    let (status, value) = json_response(app, missing).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(value["error"]["code"], "job_not_found");
+}
+
+#[tokio::test]
+async fn reviews_validate_the_game_and_run_as_jobs() {
+    let (_dir, app) = test_app();
+    let illegal = Request::builder()
+        .method("POST")
+        .uri("/api/v1/review")
+        .header("content-type", "application/json")
+        .body(Body::from(json!({ "pgn": "1. e4 e5 2. Ke3" }).to_string()))
+        .unwrap();
+    let (status, value) = json_response(app.clone(), illegal).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(value["error"]["code"], "invalid_request");
+
+    let submit = Request::builder()
+        .method("POST")
+        .uri("/api/v1/review")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "review_id": "game-1", "moves": ["e2e4", "e7e5"] }).to_string(),
+        ))
+        .unwrap();
+    let (status, value) = json_response(app.clone(), submit).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(value["kind"], "review");
+    assert_eq!(value["progress"]["plies_total"], 3);
+
+    let mut job = Value::Null;
+    for _ in 0..50 {
+        let poll = Request::builder()
+            .method("GET")
+            .uri("/api/v1/jobs/game-1")
+            .body(Body::empty())
+            .unwrap();
+        job = json_response(app.clone(), poll).await.1;
+        if job["state"] == "failed" {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    assert_eq!(job["state"], "failed");
+    assert_eq!(job["error"]["code"], "engine_not_configured");
+    assert_eq!(job["progress"]["plies_analysed"], 0);
+}
+
+#[tokio::test]
+async fn analysis_cache_can_be_inspected_and_cleared() {
+    let (dir, app) = test_app();
+    let request = Request::builder()
+        .uri("/api/v1/cache")
+        .body(Body::empty())
+        .unwrap();
+
+    let (status, value) = json_response(app.clone(), request).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(value["memory_entries"], 0);
+    assert_eq!(value["disk"]["enabled"], true);
+    assert_eq!(value["disk"]["entries"], 0);
+    assert_eq!(value["disk"]["max_bytes"], 64 * 1024 * 1024);
+    assert_eq!(value["disk"]["min_depth"], 20);
+    assert!(dir.path().join("data").join("analysis-cache").is_dir());
+
+    let request = Request::builder()
+        .method("DELETE")
+        .uri("/api/v1/cache")
+        .body(Body::empty())
+        .unwrap();
+
+    let (status, value) = json_response(app, request).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(value["disk"]["size_bytes"], 0);
 }
 
 #[tokio::test]
@@ -156,3 +652,90 @@ async fn update_settings_rejects_invalid_config_with_structured_error() {
     assert_eq!(value["ok"], false);
     assert_eq!(value["error"]["code"], "invalid_request");
 }
+
+#[tokio::test]
+async fn analyze_stream_ends_with_structured_error_event() {
+    let (_dir, app) = test_app();
+    let request = Request::builder()
+        .method("GET")
+        .uri("/api/v1/analyze/stream?fen=not%20a%20fen&depth=12")
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.oneshot(request).await.expect("response");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("body bytes");
+    let body = String::from_utf8(body.to_vec()).expect("utf8 body");
+    assert!(body.contains("event: error"));
+    assert!(body.contains("\"code\":\"invalid_fen\""));
+}
+
+#[tokio::test]
+async fn infinite_session_endpoints_report_missing_session() {
+    let (_dir, app) = test_app();
+    let request = Request::builder()
+        .method("GET")
+        .uri("/api/v1/analyze/infinite")
+        .body(Body::empty())
+        .unwrap();
+
+    let (status, value) = json_response(app.clone(), request).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(value["error"]["code"], "no_analysis_session");
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/v1/analyze/infinite/stop")
+        .body(Body::empty())
+        .unwrap();
+
+    let (status, value) = json_response(app, request).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(value["error"]["code"], "no_analysis_session");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn a_dropped_analysis_stream_stops_a_silent_search() {
+    use std::time::{Duration, Instant};
+
+    let dir = tempfile::tempdir().expect("temp dir");
+    // The search prints no info lines and runs until it is told to stop.
+    let engine = common::fake_engine(dir.path(), "go*) read -r next; echo 'bestmove e2e4' ;;");
+    let engine = common::manager(dir.path(), engine, |_| {}).await;
+    let app = Router::new()
+        .nest("/api/v1", api_routes())
+        .with_state(AppState::new(common::store(dir.path()), engine.clone()));
+
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/v1/analyze/stream")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({
+                "fen": common::START,
+                "depth": 5,
+                "max_think_time_ms": 60000,
+            })
+            .to_string(),
+        ))
+        .unwrap();
+    let response = app.oneshot(request).await.expect("response");
+
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while engine.status().status != "analyzing" {
+        assert!(Instant::now() < deadline, "the search never started");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    drop(response);
+
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while engine.status().status != "ready" {
+        assert!(Instant::now() < deadline, "the search kept its worker");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
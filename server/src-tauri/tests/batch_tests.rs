@@ -0,0 +1,148 @@
+#[cfg(unix)]
+mod common;
+
+use roblox_chess_script_lib::{
+    api::types::{AnalyzeRequest, BatchAnalyzeRequest},
+    engine::batch::{parse_epd, requests, EpdLine},
+};
+
+fn batch(positions: Vec<AnalyzeRequest>, epd: Option<&str>) -> BatchAnalyzeRequest {
+    BatchAnalyzeRequest {
+        positions,
+        epd: epd.map(str::to_string),
+        batch_id: None,
+        depth: Some(12),
+        max_think_time_ms: None,
+        disregard_think_time: Some(true),
+        use_book: None,
+        use_tablebase: None,
+        include_threat: None,
+    }
+}
+
+#[test]
+fn epd_lines_become_fens_with_their_ids() {
+    let lines = parse_epd(
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 id \"King pawn\"; bm e5;\n\
+         \n\
+         8/8/8/8/8/2k5/8/KQ6 w - - hmvc 12; fmvn 40; id \"KQK\";\n\
+         not an epd\n",
+    );
+
+    assert_eq!(
+        lines,
+        [
+            EpdLine {
+                fen: "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1".to_string(),
+                id: Some("King pawn".to_string()),
+            },
+            EpdLine {
+                fen: "8/8/8/8/8/2k5/8/KQ6 w - - 12 40".to_string(),
+                id: Some("KQK".to_string()),
+            },
+            EpdLine {
+                fen: "not an epd 0 1".to_string(),
+                id: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn shared_settings_fill_in_what_positions_leave_out() {
+    let positions = vec![
+        AnalyzeRequest {
+            fen: Some("8/8/8/8/8/2k5/8/KQ6 w - - 0 1".to_string()),
+            depth: Some(20),
+            request_id: Some("deep".to_string()),
+            ..Default::default()
+        },
+        AnalyzeRequest {
+            moves: vec!["e2e4".to_string()],
+            new_game: Some(true),
+            ..Default::default()
+        },
+    ];
+
+    let requests = requests(batch(positions, None)).unwrap();
+    assert_eq!(requests.len(), 2);
+    let (id, first) = &requests[0];
+    assert_eq!(id.as_deref(), Some("deep"));
+    assert_eq!(first.depth, Some(20));
+    assert_eq!(first.disregard_think_time, Some(true));
+    assert_eq!(first.new_game, Some(false));
+    let (id, second) = &requests[1];
+    assert_eq!(id, &None);
+    assert_eq!(second.depth, Some(12));
+    assert_eq!(second.new_game, Some(true));
+}
+
+#[test]
+fn a_batch_needs_exactly_one_kind_of_input() {
+    assert!(requests(batch(Vec::new(), None)).is_err());
+    assert!(requests(batch(Vec::new(), Some("\n\n"))).is_err());
+
+    let both = batch(
+        vec![AnalyzeRequest::default()],
+        Some("8/8/8/8/8/2k5/8/KQ6 w - -"),
+    );
+    assert!(requests(both).is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn a_dropped_batch_stream_releases_the_workers() {
+    use std::time::{Duration, Instant};
+
+    use axum::{body::Body, http::Request, Router};
+    use roblox_chess_script_lib::{api::routes::api_routes, app_state::AppState};
+    use tower::ServiceExt;
+
+    let dir = tempfile::tempdir().expect("temp dir");
+    // Searches run until they are told to stop.
+    let sent = dir.path().join("positions");
+    let engine = common::fake_engine(
+        dir.path(),
+        &format!(
+            "position*) echo \"$line\" >> '{}' ;;\n\
+             go*) read -r next; echo 'bestmove e2e4' ;;",
+            sent.display()
+        ),
+    );
+    let manager = common::manager(dir.path(), engine, |config| config.engine.workers = 1).await;
+    let app = Router::new()
+        .nest("/api/v1", api_routes())
+        .with_state(AppState::new(common::store(dir.path()), manager.clone()));
+
+    let position = serde_json::json!({ "fen": common::START });
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/v1/analyze/batch/stream")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            serde_json::json!({
+                "positions": [position, position, position],
+                "depth": 5,
+                "max_think_time_ms": 60000,
+            })
+            .to_string(),
+        ))
+        .unwrap();
+    let response = app.oneshot(request).await.expect("response");
+
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while manager.status().status != "analyzing" {
+        assert!(Instant::now() < deadline, "the batch never started");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    drop(response);
+
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while manager.status().status != "ready" {
+        assert!(Instant::now() < deadline, "the worker was not released");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let searched = std::fs::read_to_string(&sent).expect("positions sent");
+    assert_eq!(searched.lines().count(), 1);
+}
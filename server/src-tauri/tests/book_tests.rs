@@ -0,0 +1,122 @@
+use roblox_chess_script_lib::engine::book::{game_ply, polyglot_key, BookShelf, PolyglotBook};
+use shakmaty::{fen::Fen, CastlingMode, Chess, Square};
+
+fn position(fen: &str) -> Chess {
+    fen.parse::<Fen>()
+        .unwrap()
+        .into_position(CastlingMode::Standard)
+        .unwrap()
+}
+
+/// One Polyglot entry for a move from `from` to `to`.
+fn entry(key: u64, from: &str, to: &str, promotion: u16, weight: u16) -> Vec<u8> {
+    let from: Square = from.parse().unwrap();
+    let to: Square = to.parse().unwrap();
+    let bits = |square: Square| {
+        u16::from(u8::from(square.file())) | u16::from(u8::from(square.rank())) << 3
+    };
+    let raw_move = bits(to) | bits(from) << 6 | promotion << 12;
+
+    let mut bytes = key.to_be_bytes().to_vec();
+    bytes.extend(raw_move.to_be_bytes());
+    bytes.extend(weight.to_be_bytes());
+    bytes.extend(0u32.to_be_bytes());
+    bytes
+}
+
+#[test]
+fn keys_match_the_polyglot_reference_values() {
+    assert_eq!(polyglot_key(&Chess::default()), 0x463b_9618_1691_fc9c);
+    assert_eq!(
+        polyglot_key(&position(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        )),
+        0x823c_9b50_fd11_4196
+    );
+    assert_eq!(
+        polyglot_key(&position(
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3"
+        )),
+        0x22a4_8b5a_8e47_ff78
+    );
+}
+
+#[test]
+fn probing_returns_weighted_legal_moves() {
+    let start = polyglot_key(&Chess::default());
+    let mut bytes = Vec::new();
+    bytes.extend(entry(start, "d2", "d4", 0, 10));
+    bytes.extend(entry(start, "e2", "e4", 0, 30));
+    bytes.extend(entry(start, "g1", "f3", 0, 0));
+    // Not legal in the start position, so skipped.
+    bytes.extend(entry(start, "e2", "e5", 0, 50));
+    bytes.extend(entry(start.wrapping_add(1), "a2", "a3", 0, 99));
+    let book = PolyglotBook::from_bytes(&bytes).unwrap();
+
+    let moves = book.probe(&Chess::default(), 1);
+    let ucis: Vec<_> = moves
+        .iter()
+        .map(|book_move| book_move.uci.as_str())
+        .collect();
+    assert_eq!(ucis, ["e2e4", "d2d4"]);
+    assert_eq!(moves[0].san, "e4");
+    assert_eq!(moves[0].probability, 0.75);
+
+    assert_eq!(book.probe(&Chess::default(), 0).len(), 3);
+    assert!(book.probe(&Chess::default(), 31).is_empty());
+}
+
+#[test]
+fn castling_and_promotions_are_decoded() {
+    let castle = position("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    let promote = position("8/1P6/8/8/8/8/8/k6K w - - 0 1");
+    let mut bytes = Vec::new();
+    bytes.extend(entry(polyglot_key(&castle), "e1", "h1", 0, 2));
+    bytes.extend(entry(polyglot_key(&castle), "e1", "a1", 0, 1));
+    bytes.extend(entry(polyglot_key(&promote), "b7", "b8", 3, 1));
+    let book = PolyglotBook::from_bytes(&bytes).unwrap();
+
+    let moves = book.probe(&castle, 1);
+    assert_eq!(moves[0].uci, "e1g1");
+    assert_eq!(moves[0].san, "O-O");
+    assert_eq!(moves[1].uci, "e1c1");
+    assert_eq!(book.probe(&promote, 1)[0].uci, "b7b8r");
+}
+
+#[test]
+fn truncated_books_are_rejected() {
+    let err = PolyglotBook::from_bytes(&[0; 20]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn shelf_probes_books_in_order_and_reports_missing_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let start = polyglot_key(&Chess::default());
+    let first = dir.path().join("first.bin");
+    let second = dir.path().join("second.bin");
+    std::fs::write(&first, entry(start.wrapping_add(1), "a2", "a3", 0, 1)).unwrap();
+    std::fs::write(&second, entry(start, "c2", "c4", 0, 1)).unwrap();
+    let paths = vec![first.display().to_string(), second.display().to_string()];
+
+    let mut shelf = BookShelf::new();
+    let hit = shelf.probe(&paths, &Chess::default(), 1).unwrap().unwrap();
+    assert_eq!(hit.path, second.display().to_string());
+    assert_eq!(hit.moves[0].uci, "c2c4");
+
+    let missing = vec![dir.path().join("missing.bin").display().to_string()];
+    let err = shelf.probe(&missing, &Chess::default(), 1).unwrap_err();
+    assert!(err.to_string().contains("missing.bin"), "{err}");
+}
+
+#[test]
+fn game_ply_counts_from_the_move_counters() {
+    assert_eq!(game_ply(&Chess::default()), 0);
+    assert_eq!(
+        game_ply(&position(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        )),
+        1
+    );
+    assert_eq!(game_ply(&position("4k3/8/8/8/8/8/4P3/4K3 w - - 0 10")), 18);
+}
@@ -0,0 +1,189 @@
+use std::{fs, io::Write};
+
+use roblox_chess_script_lib::engine::{
+    analysis::RawAnalysisResult,
+    cache::{self, AnalysisCache, CacheKey, CachedAnalysis},
+    disk_cache::DiskCache,
+};
+
+fn key(zobrist: u64) -> CacheKey {
+    CacheKey {
+        zobrist,
+        history: 0,
+        movetime_ms: Some(100),
+        multipv: 4,
+        engine: "stockfish|Stockfish 18".to_string(),
+    }
+}
+
+fn analysis(depth: u32, best_move: &str) -> CachedAnalysis {
+    CachedAnalysis {
+        depth,
+        raw: RawAnalysisResult {
+            best_move: best_move.to_string(),
+            ponder: None,
+            lines: Vec::new(),
+            totals: Default::default(),
+            cancelled: false,
+        },
+        engine_name: Some("Stockfish 18".to_string()),
+    }
+}
+
+#[test]
+fn move_history_is_part_of_the_key() {
+    assert_eq!(cache::history_hash(&[]), 0);
+    assert_eq!(cache::history_hash(&[3, 5]), cache::history_hash(&[5, 3]));
+    assert_ne!(cache::history_hash(&[3]), cache::history_hash(&[3, 3]));
+
+    let mut cache = AnalysisCache::new();
+    cache.insert(key(1), analysis(18, "e2e4"), 8);
+    let repeated = CacheKey {
+        history: cache::history_hash(&[7, 7]),
+        ..key(1)
+    };
+    assert!(cache.get(&repeated, 12).is_none());
+}
+
+#[test]
+fn deeper_results_satisfy_shallower_requests() {
+    let mut cache = AnalysisCache::new();
+    cache.insert(key(1), analysis(18, "e2e4"), 8);
+
+    assert_eq!(cache.get(&key(1), 12).unwrap().raw.best_move, "e2e4");
+    assert_eq!(cache.get(&key(1), 18).unwrap().depth, 18);
+    assert!(cache.get(&key(1), 20).is_none());
+
+    let mut other_movetime = key(1);
+    other_movetime.movetime_ms = None;
+    assert!(cache.get(&other_movetime, 12).is_none());
+}
+
+#[test]
+fn shallower_results_do_not_replace_deeper_ones() {
+    let mut cache = AnalysisCache::new();
+    cache.insert(key(1), analysis(20, "e2e4"), 8);
+    cache.insert(key(1), analysis(10, "d2d4"), 8);
+
+    assert_eq!(cache.get(&key(1), 1).unwrap().raw.best_move, "e2e4");
+}
+
+#[test]
+fn least_recently_used_entries_are_evicted() {
+    let mut cache = AnalysisCache::new();
+    cache.insert(key(1), analysis(10, "e2e4"), 2);
+    cache.insert(key(2), analysis(10, "d2d4"), 2);
+    cache.get(&key(1), 10);
+    cache.insert(key(3), analysis(10, "c2c4"), 2);
+
+    assert_eq!(cache.len(), 2);
+    assert!(cache.get(&key(1), 10).is_some());
+    assert!(cache.get(&key(2), 10).is_none());
+
+    cache.insert(key(4), analysis(10, "g1f3"), 0);
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn reinserted_and_kept_entries_count_as_recently_used() {
+    let mut cache = AnalysisCache::new();
+    cache.insert(key(1), analysis(10, "e2e4"), 3);
+    cache.insert(key(2), analysis(20, "d2d4"), 3);
+    cache.insert(key(3), analysis(10, "c2c4"), 3);
+    // A deeper result replaces key 1; a shallower one keeps key 2's.
+    cache.insert(key(1), analysis(12, "e2e4"), 3);
+    cache.insert(key(2), analysis(8, "g1f3"), 3);
+    cache.insert(key(4), analysis(10, "b1c3"), 3);
+
+    assert_eq!(cache.len(), 3);
+    assert!(cache.get(&key(3), 1).is_none());
+    assert_eq!(cache.get(&key(1), 12).unwrap().depth, 12);
+    assert_eq!(cache.get(&key(2), 1).unwrap().raw.best_move, "d2d4");
+
+    for zobrist in 5..1_005 {
+        cache.insert(key(zobrist), analysis(10, "e2e4"), 3);
+    }
+    assert_eq!(cache.len(), 3);
+    assert!(cache.get(&key(1_002), 1).is_some());
+    assert!(cache.get(&key(1_001), 1).is_none());
+}
+
+#[test]
+fn disk_cache_survives_reopening() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let mut cache = DiskCache::open(dir.path()).unwrap();
+    cache.insert(key(1), analysis(24, "e2e4"), 1 << 20).unwrap();
+    cache.insert(key(1), analysis(30, "d2d4"), 1 << 20).unwrap();
+    cache.insert(key(1), analysis(26, "c2c4"), 1 << 20).unwrap();
+    drop(cache);
+
+    let mut cache = DiskCache::open(dir.path()).unwrap();
+    assert_eq!(cache.len(), 1);
+    let hit = cache.get(&key(1), 30).unwrap().unwrap();
+    assert_eq!(hit.raw.best_move, "d2d4");
+    assert_eq!(hit.engine_name.as_deref(), Some("Stockfish 18"));
+    assert!(cache.get(&key(1), 31).unwrap().is_none());
+    assert!(cache.get(&key(2), 1).unwrap().is_none());
+}
+
+#[test]
+fn disk_cache_skips_corrupt_records_on_load() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let mut cache = DiskCache::open(dir.path()).unwrap();
+    cache.insert(key(1), analysis(24, "e2e4"), 1 << 20).unwrap();
+    drop(cache);
+
+    let log = dir.path().join("analyses.jsonl");
+    let mut file = fs::OpenOptions::new().append(true).open(&log).unwrap();
+    file.write_all(b"{not json}\n{\"key\":").unwrap();
+    drop(file);
+
+    let mut cache = DiskCache::open(dir.path()).unwrap();
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.skipped_records(), 2);
+    cache.insert(key(2), analysis(24, "d2d4"), 1 << 20).unwrap();
+    drop(cache);
+
+    let mut cache = DiskCache::open(dir.path()).unwrap();
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.skipped_records(), 1);
+    assert_eq!(
+        cache.get(&key(2), 24).unwrap().unwrap().raw.best_move,
+        "d2d4"
+    );
+}
+
+#[test]
+fn disk_cache_evicts_least_recently_used_past_its_cap() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let mut cache = DiskCache::open(dir.path()).unwrap();
+    cache.insert(key(1), analysis(24, "e2e4"), 1 << 20).unwrap();
+    let record_len = cache.size_bytes();
+    let max_bytes = record_len * 3 + record_len / 2;
+
+    cache
+        .insert(key(2), analysis(24, "d2d4"), max_bytes)
+        .unwrap();
+    cache
+        .insert(key(3), analysis(24, "c2c4"), max_bytes)
+        .unwrap();
+    cache.get(&key(1), 24).unwrap();
+    cache
+        .insert(key(4), analysis(24, "g1f3"), max_bytes)
+        .unwrap();
+
+    assert!(cache.size_bytes() <= max_bytes);
+    assert!(cache.get(&key(1), 24).unwrap().is_some());
+    assert!(cache.get(&key(2), 24).unwrap().is_none());
+    assert!(cache.get(&key(4), 24).unwrap().is_some());
+    drop(cache);
+
+    let mut cache = DiskCache::open(dir.path()).unwrap();
+    assert_eq!(cache.skipped_records(), 0);
+    assert!(cache.get(&key(1), 24).unwrap().is_some());
+
+    cache.clear().unwrap();
+    assert!(cache.is_empty());
+    assert_eq!(cache.size_bytes(), 0);
+    assert!(DiskCache::open(dir.path()).unwrap().is_empty());
+}
@@ -0,0 +1,56 @@
+use std::{fs, os::unix::fs::PermissionsExt, path::Path};
+
+use roblox_chess_script_lib::{
+    config::{model::AppConfig, store::ConfigStore},
+    engine::manager::EngineManager,
+};
+
+pub const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// Writes a shell script that answers `uci`, `isready` and `quit` like an
+/// engine, and runs `commands`, extra `case` arms matched against `$line`,
+/// for everything else.
+pub fn fake_engine(dir: &Path, commands: &str) -> String {
+    // Only files named like Stockfish are accepted as the engine.
+    let path = dir.join("stockfish");
+    let script = format!(
+        "#!/bin/sh\n\
+         while read -r line; do\n\
+         case \"$line\" in\n\
+         uci) echo 'id name FakeFish'; echo 'uciok' ;;\n\
+         isready) echo 'readyok' ;;\n\
+         quit) exit 0 ;;\n\
+         {commands}\n\
+         esac\n\
+         done\n"
+    );
+    fs::write(&path, script).expect("engine script");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).expect("permissions");
+    path.display().to_string()
+}
+
+pub fn store(dir: &Path) -> ConfigStore {
+    ConfigStore::from_paths(dir.join("config").join("config.json"), dir.join("data"))
+        .expect("store")
+}
+
+/// A manager with `engine` started, the caches off and `configure` applied.
+pub async fn manager(
+    dir: &Path,
+    engine: String,
+    configure: impl FnOnce(&mut AppConfig),
+) -> EngineManager {
+    let store = store(dir);
+    let mut config = store.load_or_default();
+    config.engine.stockfish_path = Some(engine);
+    config.analysis.cache_size = 0;
+    configure(&mut config);
+    store.save(&config).expect("save config");
+
+    let manager = EngineManager::new(store);
+    manager
+        .initialize_from_config()
+        .await
+        .expect("engine starts");
+    manager
+}
@@ -1,4 +1,7 @@
-use roblox_chess_script_lib::config::{model::AppConfig, store::ConfigStore};
+use roblox_chess_script_lib::config::{
+    model::{AppConfig, EngineProfile},
+    store::ConfigStore,
+};
 
 fn temp_store() -> (tempfile::TempDir, ConfigStore) {
     let dir = tempfile::tempdir().expect("temp dir");
@@ -51,3 +54,94 @@ fn invalid_config_is_rejected() {
     let err = store.save(&config).expect_err("invalid config should fail");
     assert!(err.to_string().contains("hash_mb"));
 }
+
+#[test]
+fn worker_count_must_be_between_one_and_eight() {
+    let mut config = AppConfig::default();
+    assert_eq!(config.engine.workers, 1);
+
+    config.engine.workers = 0;
+    assert!(config.validate().unwrap_err().contains("workers"));
+
+    config.engine.workers = 9;
+    assert!(config.validate().unwrap_err().contains("workers"));
+
+    config.engine.workers = 4;
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn book_settings_default_off_and_are_checked() {
+    let mut config: AppConfig = serde_json::from_value(serde_json::json!({
+        "server": { "host": "127.0.0.1", "port": 57250 },
+        "engine": {
+            "stockfish_path": null,
+            "hash_mb": 64,
+            "threads": 1,
+            "syzygy_paths": [],
+            "multipv": 1,
+            "auto_restart": true
+        },
+        "analysis": {
+            "difficulty_enabled": true,
+            "candidate_threshold_cp": 80,
+            "cancel_previous_on_new_request": true,
+            "min_delay_ms": 0,
+            "max_delay_ms": 0,
+            "book": { "paths": ["books/main.bin"] }
+        }
+    }))
+    .unwrap();
+    assert!(!config.analysis.book.enabled);
+    assert_eq!(config.analysis.book.max_ply, 24);
+    assert!(config.validate().is_ok());
+
+    config.analysis.book.max_ply = 0;
+    assert!(config.validate().unwrap_err().contains("max_ply"));
+
+    config.analysis.book.max_ply = 24;
+    config.analysis.book.paths.push(" ".to_string());
+    assert!(config.validate().unwrap_err().contains("book paths"));
+}
+
+#[test]
+fn extra_options_cannot_override_managed_engine_settings() {
+    let mut config = AppConfig::default();
+    config
+        .engine
+        .extra_options
+        .insert("Move Overhead".to_string(), "50".to_string());
+    config.validate().expect("unmanaged option should validate");
+
+    config
+        .engine
+        .extra_options
+        .insert("hash".to_string(), "4096".to_string());
+    let err = config.validate().expect_err("managed option should fail");
+    assert!(err.contains("hash"));
+}
+
+#[test]
+fn active_profile_must_exist_and_its_options_override_extra_options() {
+    let mut config = AppConfig::default();
+    config.engine.active_profile = Some("lc0".to_string());
+    let err = config.validate().expect_err("unknown profile should fail");
+    assert!(err.contains("lc0"));
+
+    config
+        .engine
+        .extra_options
+        .insert("Ponder".to_string(), "false".to_string());
+    config.engine.profiles.insert(
+        "lc0".to_string(),
+        EngineProfile {
+            path: "/opt/lc0/lc0".to_string(),
+            args: vec!["--backend=cuda".to_string()],
+            working_dir: Some("/opt/lc0".to_string()),
+            options: [("Ponder".to_string(), "true".to_string())].into(),
+        },
+    );
+    config.validate().expect("profile config should validate");
+
+    assert_eq!(config.uci_options()["Ponder"], "true");
+}
@@ -0,0 +1,88 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::{fs, path::Path};
+
+use roblox_chess_script_lib::{
+    api::types::AnalyzeRequest,
+    engine::{
+        manager::{EngineManager, EngineManagerError},
+        stockfish::StockfishError,
+    },
+};
+
+use common::START;
+
+/// A manager whose engine starts normally but exits on every `position`
+/// command, noting each one in `positions` next to it.
+async fn manager(dir: &Path, retry_after_crash: bool) -> EngineManager {
+    let position = format!(
+        "position*) echo \"$line\" >> '{}'; exit 1 ;;",
+        dir.join("positions").display()
+    );
+    let engine = common::fake_engine(dir, &position);
+    common::manager(dir, engine, |config| {
+        config.engine.auto_restart = true;
+        config.engine.retry_after_crash = retry_after_crash;
+    })
+    .await
+}
+
+fn positions_sent(dir: &Path) -> usize {
+    fs::read_to_string(dir.join("positions"))
+        .map(|text| text.lines().count())
+        .unwrap_or(0)
+}
+
+fn request() -> AnalyzeRequest {
+    AnalyzeRequest {
+        fen: Some(START.to_string()),
+        depth: Some(5),
+        max_think_time_ms: Some(1000),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn a_crashed_search_is_retried_exactly_once() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let manager = manager(dir.path(), true).await;
+
+    let result = manager.analyze(request()).await;
+
+    assert!(matches!(
+        result,
+        Err(EngineManagerError::Stockfish(StockfishError::EngineExited))
+    ));
+    assert_eq!(positions_sent(dir.path()), 2);
+    assert_eq!(manager.status().restart_count, 1);
+}
+
+#[tokio::test]
+async fn the_sixth_crash_in_a_minute_is_not_restarted() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let manager = manager(dir.path(), false).await;
+
+    // Restarts back off 250 ms, 500 ms, 1 s, 2 s and 4 s.
+    for _ in 0..5 {
+        let result = manager.analyze(request()).await;
+        assert!(matches!(
+            result,
+            Err(EngineManagerError::Stockfish(StockfishError::EngineExited))
+        ));
+    }
+    assert_eq!(manager.status().restart_count, 5);
+
+    let result = manager.analyze(request()).await;
+
+    assert!(matches!(
+        result,
+        Err(EngineManagerError::RestartLimitReached(5))
+    ));
+    assert_eq!(positions_sent(dir.path()), 6);
+    let status = manager.status();
+    assert_eq!(status.status, "crashed");
+    assert_eq!(status.restart_count, 5);
+    assert!(status.last_error.is_some());
+}
@@ -11,6 +11,7 @@ fn line(rank: u8, cp: i32) -> AnalysisLine {
         score_cp: Some(cp),
         mate: None,
         pv: vec![format!("move{rank}")],
+        ..Default::default()
     }
 }
 
@@ -22,6 +23,7 @@ fn mate_line(rank: u8, mate: i32) -> AnalysisLine {
         score_cp: None,
         mate: Some(mate),
         pv: vec![format!("mate{rank}")],
+        ..Default::default()
     }
 }
 
@@ -0,0 +1,48 @@
+use roblox_chess_script_lib::engine::game_over::{detect, GameOutcome, GameOverReason};
+use shakmaty::{fen::Fen, CastlingMode, Chess};
+
+fn position(fen: &str) -> Chess {
+    fen.parse::<Fen>()
+        .unwrap()
+        .into_position(CastlingMode::Standard)
+        .unwrap()
+}
+
+#[test]
+fn playable_positions_are_not_over() {
+    let start = position("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(detect(&start), None);
+}
+
+#[test]
+fn checkmate_names_the_winner() {
+    let mated = position("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+    let game_over = detect(&mated).unwrap();
+    assert_eq!(game_over.outcome, GameOutcome::BlackWins);
+    assert_eq!(game_over.reason, GameOverReason::Checkmate);
+}
+
+#[test]
+fn draws_report_their_reason() {
+    let cases = [
+        ("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", GameOverReason::Stalemate),
+        (
+            "8/8/4k3/8/8/3NK3/8/8 w - - 0 1",
+            GameOverReason::InsufficientMaterial,
+        ),
+        (
+            "8/8/4k3/8/8/3RK3/8/8 w - - 100 80",
+            GameOverReason::FiftyMoveRule,
+        ),
+        (
+            "8/8/4k3/8/8/3RK3/8/8 w - - 150 80",
+            GameOverReason::SeventyFiveMoveRule,
+        ),
+    ];
+
+    for (fen, reason) in cases {
+        let game_over = detect(&position(fen)).unwrap();
+        assert_eq!(game_over.outcome, GameOutcome::Draw, "{fen}");
+        assert_eq!(game_over.reason, reason, "{fen}");
+    }
+}
@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+use roblox_chess_script_lib::engine::{
+    jobs::{JobKind, JobState, JobStore},
+    manager::EngineManagerError,
+};
+
+fn failed() -> EngineManagerError {
+    EngineManagerError::InvalidRequest("no engine".to_string())
+}
+
+#[test]
+fn finished_jobs_expire_without_another_insert() {
+    let store = JobStore::new();
+    store
+        .insert("short", JobKind::Analysis, 10, Duration::from_millis(50))
+        .unwrap();
+    store.finish("short", Err(failed()));
+
+    let job = store.response("short", true).unwrap();
+    assert_eq!(job.state, JobState::Failed);
+    assert_eq!(job.error.unwrap().code, "invalid_request");
+
+    std::thread::sleep(Duration::from_millis(80));
+    assert!(store.response("short", true).is_none());
+    assert!(!store.remove_finished("short"));
+}
+
+#[test]
+fn running_jobs_never_expire() {
+    let store = JobStore::new();
+    store
+        .insert("running", JobKind::Review, 10, Duration::ZERO)
+        .unwrap();
+
+    std::thread::sleep(Duration::from_millis(10));
+    assert_eq!(
+        store.response("running", true).unwrap().state,
+        JobState::Running
+    );
+    assert!(store
+        .insert("running", JobKind::Review, 10, Duration::ZERO)
+        .is_err());
+    assert!(!store.remove_finished("running"));
+}
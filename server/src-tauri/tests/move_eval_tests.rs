@@ -0,0 +1,126 @@
+#[cfg(unix)]
+mod common;
+
+use roblox_chess_script_lib::{
+    api::types::AnalysisLine,
+    engine::{
+        move_eval::{judge, parse_move},
+        review::MoveClassification,
+        stockfish::SearchLimits,
+    },
+};
+use shakmaty::{CastlingMode, Chess};
+
+fn line(score_cp: Option<i32>, mate: Option<i32>) -> AnalysisLine {
+    AnalysisLine {
+        rank: 1,
+        score_cp,
+        mate,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn moves_are_read_as_uci_or_san() {
+    let position = Chess::default();
+    let uci = |text: &str| {
+        parse_move(&position, text)
+            .unwrap()
+            .to_uci(CastlingMode::Standard)
+            .to_string()
+    };
+
+    assert_eq!(uci("g1f3"), "g1f3");
+    assert_eq!(uci("Nf3"), "g1f3");
+    assert_eq!(uci(" e4 "), "e2e4");
+    assert!(parse_move(&position, "e2e5").is_err());
+    assert!(parse_move(&position, "Qh5").is_err());
+}
+
+#[test]
+fn moves_are_judged_by_their_centipawn_loss() {
+    let best = line(Some(40), None);
+
+    assert_eq!(
+        judge(Some(&best), Some(&line(Some(40), None)), true),
+        (0, MoveClassification::Best)
+    );
+    assert_eq!(
+        judge(Some(&best), Some(&line(Some(10), None)), false),
+        (30, MoveClassification::Good)
+    );
+    assert_eq!(
+        judge(Some(&best), Some(&line(Some(-80), None)), false),
+        (120, MoveClassification::Mistake)
+    );
+    assert_eq!(
+        judge(Some(&best), Some(&line(None, Some(-3))), false),
+        (1040, MoveClassification::Blunder)
+    );
+    assert_eq!(
+        judge(
+            Some(&line(None, Some(2))),
+            Some(&line(Some(900), None)),
+            false
+        ),
+        (100, MoveClassification::MissedMate)
+    );
+}
+
+#[test]
+fn searchmoves_restrict_the_go_command() {
+    let searchmoves = ["e2e4".to_string(), "d2d4".to_string()];
+    let limits = SearchLimits {
+        depth: 12,
+        max_think_time_ms: 500,
+        disregard_think_time: false,
+        searchmoves: &searchmoves,
+    };
+    assert_eq!(
+        limits.go_command(),
+        "go depth 12 movetime 500 searchmoves e2e4 d2d4"
+    );
+
+    let limits = SearchLimits {
+        disregard_think_time: true,
+        searchmoves: &[],
+        ..limits
+    };
+    assert_eq!(limits.go_command(), "go depth 12");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn a_move_the_engine_does_not_score_is_not_judged() {
+    use roblox_chess_script_lib::{
+        api::types::EvaluateMoveRequest,
+        engine::{manager::EngineManagerError, stockfish::StockfishError},
+    };
+
+    let dir = tempfile::tempdir().expect("temp dir");
+    // Full searches score e2e4; searches restricted to a move print no line.
+    let engine = common::fake_engine(
+        dir.path(),
+        "go*searchmoves*) echo 'bestmove d2d4' ;;\n\
+         go*) echo 'info depth 1 score cp 20 pv e2e4'; echo 'bestmove e2e4' ;;",
+    );
+    let manager = common::manager(dir.path(), engine, |_| {}).await;
+
+    let result = manager
+        .evaluate_move(EvaluateMoveRequest {
+            fen: Some(common::START.to_string()),
+            start_fen: None,
+            moves: Vec::new(),
+            candidate: "d2d4".to_string(),
+            depth: Some(5),
+            max_think_time_ms: Some(1000),
+            disregard_think_time: None,
+            request_id: None,
+        })
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(EngineManagerError::Stockfish(StockfishError::MissingLine(ref uci))) if uci == "d2d4"
+    ));
+}
@@ -0,0 +1,140 @@
+use roblox_chess_script_lib::{
+    api::types::AnalysisLine,
+    engine::{
+        analysis::AnalysisAccumulator,
+        notation::{
+            annotate_line, describe_move, fen_after, replay, CastleSide, PieceColor, PieceRole,
+        },
+        uci::UciPosition,
+    },
+};
+
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+fn line(pv: &[&str]) -> AnalysisLine {
+    AnalysisLine {
+        rank: 1,
+        move_uci: pv.first().map(|uci| uci.to_string()),
+        score_cp: Some(20),
+        pv: pv.iter().map(|uci| uci.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn pv_is_numbered_from_the_side_to_move() {
+    let position = replay(&UciPosition {
+        fen: START.to_string(),
+        moves: vec!["e2e4".to_string()],
+    })
+    .unwrap();
+    let mut black = line(&["e7e5", "g1f3", "b8c6"]);
+    annotate_line(&position, &mut black);
+
+    assert_eq!(black.move_san.as_deref(), Some("e5"));
+    assert_eq!(black.pv_san, ["e5", "Nf3", "Nc6"]);
+    assert_eq!(black.pv_text.as_deref(), Some("1... e5 2. Nf3 Nc6"));
+    assert_eq!(black.pv_illegal_index, None);
+
+    let position = replay(&UciPosition::from_fen(
+        "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+    ))
+    .unwrap();
+    let mut white = line(&["e1g1", "f6e4", "f1e1"]);
+    annotate_line(&position, &mut white);
+
+    assert_eq!(white.pv_text.as_deref(), Some("4. O-O Nxe4 5. Re1"));
+}
+
+#[test]
+fn checks_and_mates_are_marked() {
+    let position = replay(&UciPosition {
+        fen: START.to_string(),
+        moves: ["f2f3", "e7e5", "g2g4"].map(String::from).to_vec(),
+    })
+    .unwrap();
+    let mut mate = line(&["d8h4"]);
+    annotate_line(&position, &mut mate);
+
+    assert_eq!(mate.pv_text.as_deref(), Some("2... Qh4#"));
+}
+
+#[test]
+fn illegal_pv_moves_are_flagged_not_included() {
+    let position = replay(&UciPosition::from_fen(START)).unwrap();
+    let mut broken = line(&["e2e4", "e2e4", "e7e5"]);
+    annotate_line(&position, &mut broken);
+
+    assert_eq!(broken.pv_san, ["e4"]);
+    assert_eq!(broken.pv_text.as_deref(), Some("1. e4"));
+    assert_eq!(broken.pv_illegal_index, Some(1));
+
+    let mut hopeless = line(&["e7e5"]);
+    annotate_line(&position, &mut hopeless);
+    assert_eq!(hopeless.move_san, None);
+    assert_eq!(hopeless.pv_text, None);
+    assert_eq!(hopeless.pv_illegal_index, Some(0));
+}
+
+#[test]
+fn fen_after_plays_the_move() {
+    let position = replay(&UciPosition::from_fen(START)).unwrap();
+
+    assert_eq!(
+        fen_after(&position, "e2e4").as_deref(),
+        Some("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+    );
+    assert_eq!(fen_after(&position, "e2e5"), None);
+}
+
+#[test]
+fn accumulator_adds_san_for_its_position() {
+    let mut acc = AnalysisAccumulator::for_position(&UciPosition::from_fen(START));
+    let line = acc.update(line(&["g1f3", "d7d5"])).unwrap();
+
+    assert_eq!(line.pv_text.as_deref(), Some("1. Nf3 d5"));
+}
+
+#[test]
+fn castling_moves_name_both_king_and_rook_squares() {
+    let position = replay(&UciPosition::from_fen(
+        "r3k2r/pppq1ppp/2npbn2/4p3/2B1P3/2NPBN2/PPPQ1PPP/R3K2R b KQkq - 4 8",
+    ))
+    .unwrap();
+    let castle = describe_move(&position, "e8c8").unwrap();
+
+    assert_eq!(castle.san, "O-O-O");
+    assert_eq!(castle.color, PieceColor::Black);
+    assert_eq!(castle.piece, PieceRole::King);
+    assert_eq!(castle.from.square, "e8");
+    assert_eq!(castle.to.square, "c8");
+    assert_eq!((castle.to.file, castle.to.rank), (2, 7));
+    let castling = castle.castling.unwrap();
+    assert_eq!(castling.side, CastleSide::QueenSide);
+    assert_eq!(castling.rook_from.square, "a8");
+    assert_eq!(castling.rook_to.square, "d8");
+    assert_eq!(castle.captured, None);
+}
+
+#[test]
+fn en_passant_and_promotion_are_spelled_out() {
+    let position = replay(&UciPosition::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2")).unwrap();
+    let en_passant = describe_move(&position, "e5d6").unwrap();
+
+    assert!(en_passant.en_passant);
+    assert_eq!(en_passant.to.square, "d6");
+    assert_eq!(en_passant.captured, Some(PieceRole::Pawn));
+    assert_eq!(en_passant.capture_square.unwrap().square, "d5");
+
+    let position = replay(&UciPosition::from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1")).unwrap();
+    let promotion = describe_move(&position, "a7b8q").unwrap();
+
+    assert_eq!(promotion.san, "axb8=Q+");
+    assert_eq!(promotion.piece, PieceRole::Pawn);
+    assert_eq!(promotion.promotion, Some(PieceRole::Queen));
+    assert_eq!(promotion.captured, Some(PieceRole::Rook));
+    assert_eq!(promotion.capture_square.unwrap().square, "b8");
+    assert!(promotion.castling.is_none());
+
+    assert!(describe_move(&position, "a7a8").is_none());
+}
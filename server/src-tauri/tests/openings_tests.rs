@@ -0,0 +1,69 @@
+use roblox_chess_script_lib::engine::{
+    openings::{Opening, OpeningTable},
+    uci::UciPosition,
+};
+
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+fn game(moves: &[&str]) -> UciPosition {
+    UciPosition {
+        fen: START.to_string(),
+        moves: moves.iter().map(|uci| uci.to_string()).collect(),
+    }
+}
+
+#[test]
+fn every_bundled_line_replays() {
+    let bundled = include_str!("../src/engine/openings.tsv");
+    let rows = bundled.lines().skip(1).count();
+
+    let mut table = OpeningTable::default();
+    assert_eq!(table.add_tsv(bundled), rows);
+    assert_eq!(table.len(), OpeningTable::bundled().len());
+}
+
+#[test]
+fn transpositions_find_the_same_opening() {
+    let table = OpeningTable::bundled();
+    let nimzo = table
+        .classify(&game(&["c2c4", "g8f6", "b1c3", "e7e6", "d2d4", "f8b4"]))
+        .unwrap();
+
+    assert_eq!(nimzo.eco, "E20");
+    assert_eq!(nimzo.name, "Nimzo-Indian Defense");
+    assert_eq!(nimzo.ply, 6);
+}
+
+#[test]
+fn positions_off_the_table_keep_the_last_named_opening() {
+    let table = OpeningTable::bundled();
+    let opening = table
+        .classify(&game(&["e2e4", "e7e5", "d1h5", "b8c6"]))
+        .unwrap();
+    assert_eq!(opening.eco, "C20");
+
+    assert_eq!(table.classify(&game(&[])), None);
+}
+
+#[test]
+fn data_dir_tables_override_the_bundled_names() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("extra.tsv"),
+        "eco\tname\tpgn\nC20\tOpen Game\t1. e4 e5\nZ99\tBroken\t1. e5\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "C20\tIgnored\t1. e4 e5\n").unwrap();
+
+    let table = OpeningTable::load(dir.path());
+
+    assert_eq!(
+        table.classify(&game(&["e2e4", "e7e5"])),
+        Some(Opening {
+            eco: "C20".to_string(),
+            name: "Open Game".to_string(),
+            ply: 2,
+        })
+    );
+    assert_eq!(table.len(), OpeningTable::bundled().len());
+}
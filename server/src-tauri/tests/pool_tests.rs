@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use roblox_chess_script_lib::engine::{manager::EngineManagerError, pool::EnginePool};
+
+#[tokio::test]
+async fn a_held_worker_makes_other_requests_busy() {
+    let pool = EnginePool::new();
+    let lease = pool.lease(Duration::from_millis(10)).await.unwrap();
+    assert!(lease.worker().is_busy());
+
+    let err = pool
+        .lease(Duration::from_millis(50))
+        .await
+        .err()
+        .expect("the only worker is leased");
+    assert!(matches!(err, EngineManagerError::EngineBusy(_)), "{err}");
+    assert_eq!(err.to_api_parts().1, "engine_busy");
+}
+
+#[tokio::test]
+async fn a_waiting_request_gets_the_worker_once_it_is_released() {
+    let pool = std::sync::Arc::new(EnginePool::new());
+    let lease = pool.lease(Duration::from_millis(10)).await.unwrap();
+    let worker_id = lease.worker().id;
+
+    let waiter = {
+        let pool = pool.clone();
+        tokio::spawn(async move { pool.lease(Duration::from_secs(5)).await })
+    };
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!waiter.is_finished());
+
+    drop(lease);
+    let lease = waiter.await.unwrap().expect("the released worker");
+    assert_eq!(lease.worker().id, worker_id);
+}
@@ -0,0 +1,90 @@
+use roblox_chess_script_lib::engine::{
+    notation::PieceRole,
+    position_report::{build, CastlingRights, GamePhase},
+};
+use shakmaty::{fen::Fen, CastlingMode, Chess};
+
+fn position(fen: &str) -> Chess {
+    fen.parse::<Fen>()
+        .unwrap()
+        .into_position(CastlingMode::Standard)
+        .unwrap()
+}
+
+#[test]
+fn start_position_is_a_quiet_opening() {
+    let report = build(&Chess::default());
+
+    assert_eq!(report.legal_move_count, 20);
+    assert_eq!(report.phase, GamePhase::Opening);
+    assert_eq!(report.phase_score, 24);
+    assert_eq!(report.material.white.points, 39);
+    assert_eq!(report.material.black.pawns, 8);
+    assert!(report.material.white.bishop_pair);
+    assert_eq!(report.material.balance, 0);
+    assert!(report.hanging.white.is_empty() && report.hanging.black.is_empty());
+    assert!(report.pinned.white.is_empty() && report.pinned.black.is_empty());
+    assert!(report.checks.is_empty() && report.captures.is_empty());
+    assert_eq!(
+        report.castling.black,
+        CastlingRights {
+            king_side: true,
+            queen_side: true,
+        }
+    );
+    assert_eq!(report.en_passant, None);
+}
+
+#[test]
+fn pins_hanging_pieces_checks_and_captures_are_found() {
+    let report = build(&position("r3k3/8/2n5/1B6/8/8/8/4K2R w Kq - 0 20"));
+
+    assert_eq!(report.phase, GamePhase::Endgame);
+    assert_eq!(report.phase_score, 6);
+    assert_eq!(report.material.balance, 0);
+
+    let pinned = &report.pinned.black;
+    assert_eq!(pinned.len(), 1);
+    assert_eq!(pinned[0].piece, PieceRole::Knight);
+    assert_eq!(pinned[0].square.square, "c6");
+    assert_eq!(pinned[0].pinned_by.square, "b5");
+    assert!(report.pinned.white.is_empty());
+
+    let hanging: Vec<_> = report
+        .hanging
+        .black
+        .iter()
+        .map(|piece| piece.square.square.as_str())
+        .collect();
+    assert_eq!(hanging, ["c6"]);
+    assert!(report.hanging.white.is_empty());
+
+    let mut checks: Vec<_> = report.checks.iter().map(|m| m.san.as_str()).collect();
+    checks.sort();
+    assert_eq!(checks, ["Bxc6+", "Rh8+"]);
+    let captures: Vec<_> = report.captures.iter().map(|m| m.san.as_str()).collect();
+    assert_eq!(captures, ["Bxc6+"]);
+
+    assert_eq!(
+        report.castling.white,
+        CastlingRights {
+            king_side: true,
+            queen_side: false,
+        }
+    );
+    assert!(report.castling.black.queen_side);
+}
+
+#[test]
+fn en_passant_is_reported_only_when_legal() {
+    let report = build(&position(
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+    ));
+    assert_eq!(report.en_passant.unwrap().square, "f6");
+    assert!(report.captures.iter().any(|m| m.en_passant));
+
+    let report = build(&position(
+        "rnbqkbnr/ppppp1pp/8/8/5p2/8/PPPPPPPP/RNBQKBNR w KQkq - 0 3",
+    ));
+    assert_eq!(report.en_passant, None);
+}
@@ -0,0 +1,208 @@
+use roblox_chess_script_lib::{
+    api::types::{AnalysisLine, AnalysisSource, AnalyzeResponse, EngineSummary},
+    engine::{
+        game_over::{GameOutcome, GameOver, GameOverReason},
+        position_report,
+        review::{build, classify, from_moves, from_pgn, move_accuracy, MoveClassification},
+    },
+};
+use shakmaty::Chess;
+
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+fn moves(ucis: &[&str]) -> Vec<String> {
+    ucis.iter().map(|uci| uci.to_string()).collect()
+}
+
+fn eval(best_move: &str, score_cp: Option<i32>, mate: Option<i32>) -> AnalyzeResponse {
+    AnalyzeResponse {
+        ok: true,
+        request_id: "review".to_string(),
+        fen: START.to_string(),
+        best_move: Some(best_move.to_string()),
+        ponder: None,
+        best_move_detail: None,
+        ponder_detail: None,
+        fen_after_best_move: None,
+        game_over: None,
+        depth: 12,
+        time_taken_ms: 5,
+        difficulty: None,
+        lines: vec![AnalysisLine {
+            rank: 1,
+            score_cp,
+            mate,
+            pv: vec![best_move.to_string()],
+            ..Default::default()
+        }],
+        totals: Default::default(),
+        engine: EngineSummary {
+            name: Some("Stockfish 17".to_string()),
+            status: "ready".to_string(),
+        },
+        cached: false,
+        source: AnalysisSource::Engine,
+        book_moves: None,
+        tablebase: None,
+        threat: None,
+        opening: None,
+        position_report: position_report::build(&Chess::default()),
+    }
+}
+
+fn checkmated() -> AnalyzeResponse {
+    AnalyzeResponse {
+        best_move: None,
+        lines: Vec::new(),
+        game_over: Some(GameOver {
+            outcome: GameOutcome::WhiteWins,
+            reason: GameOverReason::Checkmate,
+        }),
+        ..eval("a1a1", None, None)
+    }
+}
+
+/// 1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6, before White's fourth move.
+const OPENING: [&str; 6] = ["e2e4", "e7e5", "d1h5", "b8c6", "f1c4", "g8f6"];
+
+fn opening_evals() -> Vec<AnalyzeResponse> {
+    vec![
+        eval("e2e4", Some(30), None),
+        eval("e7e5", Some(-30), None),
+        eval("g1f3", Some(40), None),
+        eval("b8c6", Some(-10), None),
+        eval("f1c4", Some(20), None),
+        eval("g7g6", Some(-30), None),
+        eval("h5f7", None, Some(1)),
+    ]
+}
+
+#[test]
+fn pgn_skips_tags_comments_variations_and_nags() {
+    let game = from_pgn(
+        r#"[Event "Casual"]
+[White "Someone"]
+
+1. e4 {best by test} e5 2. Nf3 (2. f4 exf4) Nc6 $1 3. Bc4!? Nf6
+4. 0-0 ; a comment to the end of the line
+Bc5 1-0"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        game.moves,
+        moves(&["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "e1g1", "f8c5"])
+    );
+    assert_eq!(game.sans[6], "O-O");
+    assert_eq!(game.positions.len(), 9);
+    assert_eq!(game.start_fen, START);
+}
+
+#[test]
+fn pgn_fen_tag_sets_the_start_position() {
+    let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 30";
+    let game = from_pgn(&format!(
+        "[SetUp \"1\"]\n[FEN \"{fen}\"]\n\n30... Kd7 31. e4 *"
+    ))
+    .unwrap();
+
+    assert_eq!(game.start_fen, fen);
+    assert_eq!(game.moves, moves(&["e8d7", "e2e4"]));
+}
+
+#[test]
+fn uci_castling_is_sent_in_standard_form() {
+    let ucis = ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "e1h1"];
+    let game = from_moves(START, &moves(&ucis)).unwrap();
+
+    assert_eq!(game.moves.last().map(String::as_str), Some("e1g1"));
+    assert_eq!(game.sans.last().map(String::as_str), Some("O-O"));
+}
+
+#[test]
+fn illegal_pgn_moves_are_named_in_the_error() {
+    let err = from_pgn("1. e4 e5 2. Ke3").unwrap_err();
+    assert!(err.to_string().contains("2. Ke3"), "{err}");
+
+    let err = from_moves(START, &[]).unwrap_err();
+    assert!(err.to_string().contains("no moves"), "{err}");
+}
+
+#[test]
+fn moves_are_classified_by_centipawn_loss() {
+    assert_eq!(classify(0), MoveClassification::Good);
+    assert_eq!(classify(49), MoveClassification::Good);
+    assert_eq!(classify(50), MoveClassification::Inaccuracy);
+    assert_eq!(classify(100), MoveClassification::Mistake);
+    assert_eq!(classify(300), MoveClassification::Blunder);
+
+    assert!(move_accuracy(50, 50) > 99.9);
+    assert!(move_accuracy(50, -250) < move_accuracy(50, 0));
+}
+
+#[test]
+fn review_scores_every_move_and_both_sides() {
+    let mut ucis = OPENING.to_vec();
+    ucis.push("h5f7");
+    let game = from_moves(START, &moves(&ucis)).unwrap();
+    let mut evals = opening_evals();
+    evals.push(checkmated());
+
+    let review = build("review".to_string(), &game, &evals, 12, 40);
+
+    let classes: Vec<_> = review
+        .moves
+        .iter()
+        .map(|reviewed| reviewed.classification)
+        .collect();
+    assert_eq!(
+        classes,
+        [
+            MoveClassification::Best,
+            MoveClassification::Best,
+            MoveClassification::Good,
+            MoveClassification::Best,
+            MoveClassification::Best,
+            MoveClassification::Blunder,
+            MoveClassification::Best,
+        ]
+    );
+    let blunder = &review.moves[5];
+    assert_eq!(blunder.move_san, "Nf6");
+    assert_eq!(blunder.best_move_san.as_deref(), Some("g6"));
+    assert_eq!(blunder.centipawn_loss, 970);
+    assert_eq!(review.moves[6].move_san, "Qxf7#");
+
+    assert_eq!(review.white.moves, 4);
+    assert_eq!(review.black.blunders, 1);
+    assert!(review.white.accuracy.unwrap() > review.black.accuracy.unwrap());
+    assert_eq!(review.white.average_centipawn_loss, Some(7.5));
+
+    let curve: Vec<i32> = review
+        .eval_curve
+        .iter()
+        .map(|point| point.white_cp)
+        .collect();
+    assert_eq!(curve, [30, 30, 40, 10, 20, 30, 1000, 1000]);
+    assert_eq!(
+        review.game_over.map(|game_over| game_over.reason),
+        Some(GameOverReason::Checkmate)
+    );
+}
+
+#[test]
+fn letting_a_forced_mate_go_is_a_missed_mate() {
+    let mut ucis = OPENING.to_vec();
+    ucis.push("a2a3");
+    let game = from_moves(START, &moves(&ucis)).unwrap();
+    let mut evals = opening_evals();
+    evals.push(eval("f6h5", Some(-150), None));
+
+    let review = build("review".to_string(), &game, &evals, 12, 40);
+
+    let last = review.moves.last().unwrap();
+    assert_eq!(last.classification, MoveClassification::MissedMate);
+    assert_eq!(last.eval_before_cp, 1000);
+    assert_eq!(last.eval_after_cp, 150);
+    assert_eq!(review.white.missed_mates, 1);
+}
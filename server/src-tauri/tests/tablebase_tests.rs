@@ -0,0 +1,84 @@
+use roblox_chess_script_lib::engine::tablebase::{
+    scan_folder, PieceSet, TablebaseWdl, Tablebases, TB_WIN_CP,
+};
+use shakmaty::Chess;
+
+#[test]
+fn dtz_and_the_fifty_move_counter_give_the_result() {
+    assert_eq!(TablebaseWdl::from_dtz(0, 0), TablebaseWdl::Draw);
+    assert_eq!(TablebaseWdl::from_dtz(12, 0), TablebaseWdl::Win);
+    assert_eq!(TablebaseWdl::from_dtz(-12, 0), TablebaseWdl::Loss);
+    assert_eq!(TablebaseWdl::from_dtz(60, 40), TablebaseWdl::Win);
+    assert_eq!(TablebaseWdl::from_dtz(60, 41), TablebaseWdl::CursedWin);
+    assert_eq!(TablebaseWdl::from_dtz(-101, 0), TablebaseWdl::BlessedLoss);
+
+    assert_eq!(TablebaseWdl::CursedWin.flip(), TablebaseWdl::BlessedLoss);
+    assert_eq!(TablebaseWdl::Loss.flip(), TablebaseWdl::Win);
+    assert!(TablebaseWdl::Win > TablebaseWdl::CursedWin);
+    assert!(TablebaseWdl::BlessedLoss > TablebaseWdl::Loss);
+}
+
+#[test]
+fn quicker_wins_score_higher() {
+    assert_eq!(TablebaseWdl::Win.score_cp(3), TB_WIN_CP - 3);
+    assert!(TablebaseWdl::Win.score_cp(3) > TablebaseWdl::Win.score_cp(9));
+    assert!(TablebaseWdl::Loss.score_cp(-9) > TablebaseWdl::Loss.score_cp(-3));
+    assert_eq!(TablebaseWdl::CursedWin.score_cp(120), 0);
+    assert_eq!(TablebaseWdl::Win.wdl().win, 1000);
+    assert_eq!(TablebaseWdl::BlessedLoss.wdl().draw, 1000);
+}
+
+#[test]
+fn folders_are_reported_by_piece_count() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in [
+        "KQvK.rtbw",
+        "KQvK.rtbz",
+        "KRvK.rtbw",
+        "KRPvKR.rtbw",
+        "notes.txt",
+        "KXvK.rtbw",
+    ] {
+        std::fs::write(dir.path().join(name), b"").unwrap();
+    }
+
+    let folder = scan_folder(&dir.path().display().to_string());
+
+    assert_eq!(folder.error, None);
+    assert_eq!(
+        folder.piece_sets,
+        [
+            PieceSet {
+                pieces: 3,
+                wdl_tables: 2,
+                dtz_tables: 1,
+                expected_tables: 5,
+                complete: false,
+            },
+            PieceSet {
+                pieces: 5,
+                wdl_tables: 1,
+                dtz_tables: 0,
+                expected_tables: 110,
+                complete: false,
+            },
+        ]
+    );
+
+    let missing = scan_folder(&dir.path().join("missing").display().to_string());
+    assert!(missing.error.is_some());
+    assert!(missing.piece_sets.is_empty());
+}
+
+#[test]
+fn positions_beyond_the_tables_are_not_probed() {
+    let dir = tempfile::tempdir().unwrap();
+    let paths = vec![dir.path().display().to_string()];
+    let mut tablebases = Tablebases::new();
+
+    assert_eq!(tablebases.max_pieces(&paths), 0);
+    assert!(tablebases
+        .probe(&paths, &Chess::default())
+        .unwrap()
+        .is_none());
+}
@@ -0,0 +1,77 @@
+use roblox_chess_script_lib::{
+    api::types::AnalysisLine,
+    engine::{
+        analysis::RawAnalysisResult,
+        threat::{from_search, null_move},
+    },
+};
+use shakmaty::{fen::Fen, CastlingMode, Chess, Color, EnPassantMode, Position};
+
+fn position(fen: &str) -> Chess {
+    fen.parse::<Fen>()
+        .unwrap()
+        .into_position(CastlingMode::Standard)
+        .unwrap()
+}
+
+fn search(best_move: &str, score_cp: Option<i32>, mate: Option<i32>) -> RawAnalysisResult {
+    RawAnalysisResult {
+        best_move: best_move.to_string(),
+        ponder: None,
+        lines: vec![AnalysisLine {
+            rank: 1,
+            depth: Some(10),
+            move_uci: Some(best_move.to_string()),
+            score_cp,
+            mate,
+            pv: vec![best_move.to_string()],
+            pv_san: vec!["Bxf7+".to_string()],
+            ..Default::default()
+        }],
+        totals: Default::default(),
+        cancelled: false,
+    }
+}
+
+#[test]
+fn passing_swaps_the_side_and_drops_en_passant() {
+    let passed = null_move(&position(
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+    ))
+    .unwrap();
+
+    assert_eq!(passed.turn(), Color::Black);
+    assert_eq!(
+        Fen::from_position(&passed, EnPassantMode::Always).to_string(),
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3"
+    );
+}
+
+#[test]
+fn a_side_in_check_cannot_pass() {
+    let checked = position("rnb1kbnr/pppp1ppp/8/4p3/5PPq/8/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+    assert!(null_move(&checked).is_none());
+}
+
+#[test]
+fn the_threat_gain_is_measured_against_the_current_score() {
+    // Black to move; if Black passed, White would take on f7 with check.
+    let passed = null_move(&position(
+        "r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/8/PPPP1PPP/RNBQK1NR b KQkq - 3 3",
+    ))
+    .unwrap();
+
+    let threat = from_search(&passed, &search("c4f7", Some(180), None), Some(-30)).unwrap();
+
+    assert_eq!(threat.move_san, "Bxf7+");
+    assert_eq!(threat.move_detail.from.square, "c4");
+    assert_eq!(threat.gain_cp, Some(150));
+    assert_eq!(threat.depth, Some(10));
+    assert_eq!(threat.pv_san, ["Bxf7+"]);
+
+    let mating = from_search(&passed, &search("c4f7", None, Some(3)), Some(-30)).unwrap();
+    assert_eq!(mating.mate, Some(3));
+    assert_eq!(mating.gain_cp, None);
+
+    assert!(from_search(&passed, &search("e2e5", Some(10), None), None).is_none());
+}
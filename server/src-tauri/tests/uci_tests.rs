@@ -1,8 +1,14 @@
+#[cfg(unix)]
+mod common;
+
 use roblox_chess_script_lib::{
     api::types::AnalysisLine,
     engine::{
         analysis::AnalysisAccumulator,
-        uci::{info_to_analysis_line, parse_bestmove_line, parse_info_line, UciScore},
+        uci::{
+            info_to_analysis_line, parse_bestmove_line, parse_info_line, parse_option_line,
+            ScoreBound, UciOptionKind, UciPosition, UciScore, Wdl,
+        },
     },
 };
 
@@ -31,17 +37,136 @@ fn parses_mate_info_line() {
     assert_eq!(line.move_uci.as_deref(), Some("h2h4"));
 }
 
+#[test]
+fn parses_search_statistics_bounds_and_wdl() {
+    let parsed = parse_info_line(
+        "info depth 24 seldepth 33 multipv 1 score cp 41 lowerbound wdl 120 830 50 nodes 2048000 nps 1024000 hashfull 312 tbhits 7 time 2000 pv e2e4 e7e5",
+    )
+    .unwrap();
+
+    assert_eq!(parsed.seldepth, Some(33));
+    assert_eq!(parsed.score, Some(UciScore::Cp(41)));
+    assert_eq!(parsed.bound, Some(ScoreBound::Lower));
+    assert_eq!(
+        parsed.wdl,
+        Some(Wdl {
+            win: 120,
+            draw: 830,
+            loss: 50
+        })
+    );
+    assert_eq!(parsed.nodes, Some(2_048_000));
+    assert_eq!(parsed.nps, Some(1_024_000));
+    assert_eq!(parsed.hashfull, Some(312));
+    assert_eq!(parsed.tbhits, Some(7));
+    assert_eq!(parsed.time_ms, Some(2000));
+    assert_eq!(parsed.pv, vec!["e2e4", "e7e5"]);
+
+    let current = parse_info_line("info depth 24 currmove g1f3 currmovenumber 3").unwrap();
+    assert_eq!(current.currmove.as_deref(), Some("g1f3"));
+    assert_eq!(current.currmovenumber, Some(3));
+    assert!(current.pv.is_empty());
+}
+
 #[test]
 fn parses_bestmove_with_and_without_ponder() {
     let with_ponder = parse_bestmove_line("bestmove e2e4 ponder e7e5").unwrap();
-    assert_eq!(with_ponder.0, "e2e4");
+    assert_eq!(with_ponder.0.as_deref(), Some("e2e4"));
     assert_eq!(with_ponder.1.as_deref(), Some("e7e5"));
 
     let no_ponder = parse_bestmove_line("bestmove g1f3").unwrap();
-    assert_eq!(no_ponder.0, "g1f3");
+    assert_eq!(no_ponder.0.as_deref(), Some("g1f3"));
     assert_eq!(no_ponder.1, None);
 }
 
+#[test]
+fn null_best_moves_are_not_moves() {
+    assert_eq!(parse_bestmove_line("bestmove (none)"), Some((None, None)));
+    assert_eq!(
+        parse_bestmove_line("bestmove 0000 ponder 0000"),
+        Some((None, None))
+    );
+    assert_eq!(
+        parse_bestmove_line("bestmove e1g1 ponder (none)"),
+        Some((Some("e1g1".to_string()), None))
+    );
+}
+
+#[test]
+fn parses_option_catalogue_lines() {
+    let hash =
+        parse_option_line("option name Hash type spin default 16 min 1 max 33554432").unwrap();
+    assert_eq!(hash.name, "Hash");
+    assert_eq!(
+        hash.kind,
+        UciOptionKind::Spin {
+            default: 16,
+            min: 1,
+            max: 33554432
+        }
+    );
+    assert!(hash.check_value("256").is_ok());
+    assert!(hash.check_value("0").is_err());
+
+    let overhead =
+        parse_option_line("option name Move Overhead type spin default 10 min 0 max 5000").unwrap();
+    assert_eq!(overhead.name, "Move Overhead");
+
+    let style = parse_option_line(
+        "option name Style type combo default Normal var Solid var Normal var Risky Attack",
+    )
+    .unwrap();
+    assert_eq!(
+        style.kind,
+        UciOptionKind::Combo {
+            default: "Normal".to_string(),
+            values: vec![
+                "Solid".to_string(),
+                "Normal".to_string(),
+                "Risky Attack".to_string()
+            ],
+        }
+    );
+    assert!(style.check_value("Risky Attack").is_ok());
+    assert!(style.check_value("Wild").is_err());
+
+    let path = parse_option_line("option name SyzygyPath type string default <empty>").unwrap();
+    assert_eq!(
+        path.kind,
+        UciOptionKind::String {
+            default: String::new()
+        }
+    );
+
+    let ponder = parse_option_line("option name Ponder type check default false").unwrap();
+    assert_eq!(ponder.kind, UciOptionKind::Check { default: false });
+    assert!(ponder.check_value("yes").is_err());
+
+    let clear = parse_option_line("option name Clear Hash type button").unwrap();
+    assert_eq!(clear.kind, UciOptionKind::Button);
+    assert!(clear.check_value("true").is_err());
+
+    assert!(parse_option_line("id name Stockfish 18").is_none());
+}
+
+#[test]
+fn position_command_appends_moves_only_when_present() {
+    let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(
+        UciPosition::from_fen(fen).command(),
+        format!("position fen {fen}")
+    );
+
+    let position = UciPosition {
+        fen: fen.to_string(),
+        moves: vec!["e2e4".to_string(), "c7c5".to_string()],
+    };
+    assert_eq!(
+        position.command(),
+        format!("position fen {fen} moves e2e4 c7c5")
+    );
+}
+
 #[test]
 fn accumulator_keeps_latest_line_per_rank_and_sorts_by_rank() {
     let mut acc = AnalysisAccumulator::default();
@@ -52,6 +177,7 @@ fn accumulator_keeps_latest_line_per_rank_and_sorts_by_rank() {
         score_cp: Some(20),
         mate: None,
         pv: vec!["d2d4".to_string()],
+        ..Default::default()
     });
     acc.update(AnalysisLine {
         rank: 1,
@@ -60,6 +186,7 @@ fn accumulator_keeps_latest_line_per_rank_and_sorts_by_rank() {
         score_cp: Some(30),
         mate: None,
         pv: vec!["e2e4".to_string()],
+        ..Default::default()
     });
     acc.update(AnalysisLine {
         rank: 2,
@@ -68,6 +195,7 @@ fn accumulator_keeps_latest_line_per_rank_and_sorts_by_rank() {
         score_cp: Some(25),
         mate: None,
         pv: vec!["c2c4".to_string()],
+        ..Default::default()
     });
 
     let lines = acc.into_lines();
@@ -78,3 +206,67 @@ fn accumulator_keeps_latest_line_per_rank_and_sorts_by_rank() {
     assert_eq!(lines[1].move_uci.as_deref(), Some("c2c4"));
     assert_eq!(lines[1].depth, Some(12));
 }
+
+#[test]
+fn accumulator_ignores_bound_scores_and_lines_without_pv() {
+    let mut acc = AnalysisAccumulator::default();
+    for line in [
+        "info depth 10 multipv 1 score cp 30 pv e2e4 e7e5",
+        "info depth 11 multipv 1 score cp 80 lowerbound pv d2d4",
+        "info depth 11 currmove c2c4 currmovenumber 2",
+        "info depth 11 seldepth 15 nodes 5000 nps 250000 hashfull 4",
+    ] {
+        let info = parse_info_line(line).unwrap();
+        acc.record_totals(&info);
+        acc.update(info_to_analysis_line(info));
+    }
+
+    let result = acc.into_result("e2e4".to_string(), None);
+    assert_eq!(result.lines.len(), 1);
+    assert_eq!(result.lines[0].score_cp, Some(30));
+    assert_eq!(result.lines[0].bound, None);
+    assert_eq!(result.totals.nodes, Some(5000));
+    assert_eq!(result.totals.nps, Some(250_000));
+    assert_eq!(result.totals.hashfull, Some(4));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn castling_sent_as_king_takes_rook_reaches_the_engine_in_standard_form() {
+    use common::START;
+    use roblox_chess_script_lib::api::types::AnalyzeRequest;
+
+    let dir = tempfile::tempdir().expect("temp dir");
+    let sent = dir.path().join("positions");
+    let engine = common::fake_engine(
+        dir.path(),
+        &format!(
+            "position*) echo \"$line\" >> '{}' ;;\n\
+             go*) echo 'info depth 1 score cp 20 pv d7d6'; echo 'bestmove d7d6' ;;",
+            sent.display()
+        ),
+    );
+    let manager = common::manager(dir.path(), engine, |_| {}).await;
+    let moves = ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "e1h1"];
+
+    let response = manager
+        .analyze(AnalyzeRequest {
+            moves: moves.iter().map(|uci| uci.to_string()).collect(),
+            depth: Some(5),
+            max_think_time_ms: Some(1000),
+            ..Default::default()
+        })
+        .await
+        .expect("analysis");
+
+    assert_eq!(
+        std::fs::read_to_string(&sent)
+            .expect("position sent")
+            .trim(),
+        format!("position fen {START} moves e2e4 e7e5 g1f3 b8c6 f1c4 g8f6 e1g1")
+    );
+    assert_eq!(
+        response.fen,
+        "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 5 4"
+    );
+}
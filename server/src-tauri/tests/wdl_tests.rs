@@ -0,0 +1,64 @@
+use roblox_chess_script_lib::{
+    api::types::AnalysisLine,
+    engine::{
+        uci::{UciPosition, Wdl},
+        wdl::{complete_line, estimate},
+    },
+};
+
+#[test]
+fn estimate_is_symmetric_and_sums_to_one_thousand() {
+    let equal = estimate(Some(0), None).unwrap();
+    assert_eq!(equal.win, equal.loss);
+    assert_eq!(equal.win + equal.draw + equal.loss, 1000);
+
+    let ahead = estimate(Some(250), None).unwrap();
+    let behind = estimate(Some(-250), None).unwrap();
+    assert_eq!(ahead.win, behind.loss);
+    assert_eq!(ahead.loss, behind.win);
+    assert!(ahead.win > equal.win);
+
+    let mated = estimate(None, Some(-2)).unwrap();
+    assert_eq!(mated.loss, 1000);
+    assert_eq!(estimate(None, None), None);
+}
+
+#[test]
+fn complete_line_keeps_engine_wdl_and_flips_scores_for_black() {
+    let mut line = AnalysisLine {
+        rank: 1,
+        score_cp: Some(45),
+        wdl: Some(Wdl {
+            win: 300,
+            draw: 650,
+            loss: 50,
+        }),
+        pv: vec!["e7e5".to_string()],
+        ..Default::default()
+    };
+
+    complete_line(&mut line, false);
+
+    assert_eq!(line.wdl.unwrap().win, 300);
+    assert_eq!(line.white_score_cp, Some(-45));
+    assert_eq!(
+        line.white_wdl,
+        Some(Wdl {
+            win: 50,
+            draw: 650,
+            loss: 300
+        })
+    );
+}
+
+#[test]
+fn side_to_move_follows_the_move_list() {
+    let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert!(UciPosition::from_fen(fen).white_to_move());
+
+    let position = UciPosition {
+        fen: fen.to_string(),
+        moves: vec!["e2e4".to_string()],
+    };
+    assert!(!position.white_to_move());
+}
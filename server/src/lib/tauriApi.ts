@@ -1,9 +1,12 @@
 import { invoke } from '@tauri-apps/api/core';
 
 import type {
+  AnalysisCacheResponse,
   AppConfig,
   BotTimingPreset,
+  CancelAnalysisResponse,
   DetectStockfishResponse,
+  EngineOptionsResponse,
   GenericOkResponse,
   HistoryItem,
   UiStatusResponse,
@@ -39,8 +42,28 @@ export function restartEngine() {
   return invoke<GenericOkResponse>('restart_engine');
 }
 
+export function setEngineProfile(profile: string | null) {
+  return invoke<GenericOkResponse>('set_engine_profile', { profile });
+}
+
+export function getEngineOptions() {
+  return invoke<EngineOptionsResponse>('get_engine_options');
+}
+
 export function cancelAnalysis() {
-  return invoke<GenericOkResponse>('cancel_analysis');
+  return invoke<CancelAnalysisResponse>('cancel_analysis');
+}
+
+export function cancelAnalysisRequest(requestId: string) {
+  return invoke<CancelAnalysisResponse>('cancel_analysis_request', { requestId });
+}
+
+export function getAnalysisCache() {
+  return invoke<AnalysisCacheResponse>('get_analysis_cache');
+}
+
+export function clearAnalysisCache() {
+  return invoke<AnalysisCacheResponse>('clear_analysis_cache');
 }
 
 export function detectStockfish() {
@@ -12,6 +12,11 @@ export interface AppConfig {
     syzygy_paths: string[];
     multipv: number;
     auto_restart: boolean;
+    retry_after_crash: boolean;
+    workers: number;
+    extra_options: Record<string, string>;
+    profiles: Record<string, EngineProfile>;
+    active_profile: string | null;
   };
   analysis: {
     difficulty_enabled: boolean;
@@ -20,9 +25,27 @@ export interface AppConfig {
     min_delay_ms: number;
     max_delay_ms: number;
     timing_preset: BotTimingPreset;
+    queue_timeout_ms: number;
+    job_retention_secs: number;
+    cache_size: number;
+    disk_cache_max_mb: number;
+    disk_cache_min_depth: number;
+    book: {
+      enabled: boolean;
+      paths: string[];
+      min_weight: number;
+      max_ply: number;
+    };
   };
 }
 
+export interface EngineProfile {
+  path: string;
+  args: string[];
+  working_dir: string | null;
+  options: Record<string, string>;
+}
+
 export interface Difficulty {
   score: number;
   label: string;
@@ -34,8 +57,20 @@ export interface EngineStatus {
   status: string;
   name: string | null;
   stockfish_path: string | null;
+  profile: string | null;
   last_error: string | null;
   current_job_id: string | null;
+  restart_count: number;
+  last_crash_reason: string | null;
+  workers: EngineWorkerStatus[];
+}
+
+export interface EngineWorkerStatus {
+  index: number;
+  status: string;
+  current_job_id: string | null;
+  restart_count: number;
+  last_error: string | null;
 }
 
 export interface HistoryItem {
@@ -47,6 +82,13 @@ export interface HistoryItem {
   time_taken_ms: number | null;
   status: string;
   error: string | null;
+  opening: Opening | null;
+}
+
+export interface Opening {
+  eco: string;
+  name: string;
+  ply: number;
 }
 
 export interface UiStatusResponse {
@@ -67,6 +109,39 @@ export interface GenericOkResponse {
   message: string;
 }
 
+export interface CancelAnalysisResponse {
+  ok: boolean;
+  message: string;
+  cancelled: string[];
+}
+
+export interface AnalysisCacheResponse {
+  ok: boolean;
+  memory_entries: number;
+  disk: {
+    enabled: boolean;
+    path: string;
+    entries: number;
+    size_bytes: number;
+    max_bytes: number;
+    min_depth: number;
+    skipped_records: number;
+  };
+}
+
+export type UciOption =
+  | { name: string; type: 'spin'; default: number; min: number; max: number }
+  | { name: string; type: 'check'; default: boolean }
+  | { name: string; type: 'combo'; default: string; values: string[] }
+  | { name: string; type: 'string'; default: string }
+  | { name: string; type: 'button' };
+
+export interface EngineOptionsResponse {
+  ok: boolean;
+  engine_name: string | null;
+  options: UciOption[];
+}
+
 export interface DetectStockfishResponse {
   ok: boolean;
   path: string | null;